@@ -12,14 +12,36 @@ fn main() {
         frequency: Frequency::Daily,
         interval: 2,
         count: Some(5),
-        until: None,
+        ..RecurrenceRule::new(Frequency::Daily)
     };
 
-    // Create a recurrence generator
-    let mut generator = RecurrenceGenerator::new(start_date, rule);
-
-    // Generate and display the occurrences
-    while let Some(occurrence) = generator.next() {
+    // Occurrences are generated lazily by iterating the generator
+    for occurrence in RecurrenceGenerator::new(start_date, rule) {
         println!("Next occurrence: {}", occurrence);
     }
+
+    // Rules can also be read from and written back to RFC 5545 RRULE text
+    let rule: RecurrenceRule = "FREQ=MONTHLY;BYDAY=-1FR;COUNT=3".parse().unwrap();
+    println!("Parsed rule: {}", rule);
+
+    for occurrence in RecurrenceGenerator::new(start_date, rule) {
+        println!("Last Friday of the month: {}", occurrence);
+    }
+
+    // Rules without COUNT or UNTIL never end, so take only what is needed
+    let weekly: RecurrenceRule = "FREQ=WEEKLY;BYDAY=MO".parse().unwrap();
+    for occurrence in RecurrenceGenerator::new(start_date, weekly).take(3) {
+        println!("Monday: {}", occurrence);
+    }
+
+    // Expanding in a named zone keeps the local time fixed across DST changes
+    let new_york = DateX::parse("2024-03-08 14:00:00", "%Y-%m-%d %H:%M:%S")
+        .unwrap()
+        .to_timezone("America/New_York")
+        .unwrap();
+    let daily: RecurrenceRule = "FREQ=DAILY;COUNT=4".parse().unwrap();
+    for occurrence in RecurrenceGenerator::in_timezone(&new_york, daily) {
+        let local = occurrence.to_timezone("America/New_York").unwrap();
+        println!("New York meeting: {} ({} UTC)", local, occurrence);
+    }
 }
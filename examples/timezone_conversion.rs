@@ -1,11 +1,9 @@
 use date_rs::DateX;
 
 fn main() {
-
     let date = DateX::now();
     println!("Current UTC Time: {}", date);
 
-
     let ny_time = date
         .format_in_timezone("%Y-%m-%d %H:%M:%S", "America/New_York")
         .unwrap();
@@ -17,11 +15,17 @@ fn main() {
         .unwrap();
     println!("Time in Tokyo: {}", tokyo_time);
 
+    // Keep the zone around and work with local wall-clock time
+    let mut berlin = date.to_timezone("Europe/Berlin").unwrap();
+    println!("Time in Berlin: {} (hour {})", berlin, berlin.hour());
+    berlin.start_of_day();
+    println!("Start of the Berlin day: {}", berlin);
+    println!("Start of the Berlin day in UTC: {}", berlin.to_utc());
+
     // Get the timezone offset for New York
     let ny_offset = date.timezone_offset("America/New_York").unwrap();
     println!("New York Timezone Offset: {}", ny_offset);
 
-
     let timezones = DateX::available_timezones();
     println!("Available Time Zones (first 5): {:?}", timezones);
 }
@@ -1,18 +1,24 @@
 // src/datex.rs
+use crate::detect::{DateOrder, DetectedFormat, FormatDetector};
 use crate::durationx::DurationX;
-use crate::formatter::DateFormatter;
+use crate::error::DateError;
+use crate::formatter::FormatItems;
 use crate::locale::LocaleManager;
 use crate::manipulator::{DurationUnit, TimeManipulator};
-use crate::parser::DateParser;
-use crate::timezone::TimeZoneHandler;
+use crate::natural::NaturalParser;
+use crate::parser::{out_of_range, DateParser, ParseOptions};
+use crate::relative::RelativeFormatter;
+use crate::timezone::{LocalTimePolicy, TimeZoneHandler};
 use crate::utils::*;
-use chrono::{DateTime, Datelike, NaiveDateTime, Timelike, Utc};
+use crate::wellknown::DateFormat;
+use crate::zoned::ZonedDateX;
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc, Weekday};
 use chrono_tz::Tz;
 use std::fmt;
 use std::ops::{Add, Sub};
 
 /// A versatile date and time struct that provides extensive functionality for parsing, formatting, and manipulating dates and times.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct DateX {
     pub datetime: DateTime<Utc>,
 }
@@ -25,52 +31,143 @@ impl DateX {
         }
     }
 
-    pub fn to_timezone(&self, timezone: &str) -> Result<DateX, String> {
+    /// Converts the date to the specified time zone, keeping the zone so that
+    /// accessors and formatting use local wall-clock time.
+    pub fn to_timezone(&self, timezone: &str) -> Result<ZonedDateX, DateError> {
         match timezone.parse::<Tz>() {
-            Ok(tz) => {
-                let datetime_in_tz = self.datetime.with_timezone(&tz);
-                // Convert back to UTC for internal consistency
-                let datetime_utc = datetime_in_tz.with_timezone(&Utc);
-                Ok(DateX {
-                    datetime: datetime_utc,
-                })
-            }
-            Err(_) => Err("Invalid timezone".to_string()),
+            Ok(tz) => Ok(ZonedDateX {
+                datetime: self.datetime.with_timezone(&tz),
+            }),
+            Err(_) => Err(DateError::InvalidTimezone(timezone.to_string())),
         }
     }
 
     /// Returns the localized datetime in the specified timezone as a string.
-    pub fn format_in_timezone(&self, format: &str, timezone: &str) -> Result<String, String> {
+    pub fn format_in_timezone(&self, format: &str, timezone: &str) -> Result<String, DateError> {
         match timezone.parse::<Tz>() {
             Ok(tz) => {
                 let datetime_in_tz = self.datetime.with_timezone(&tz);
                 Ok(datetime_in_tz.format(format).to_string())
             }
-            Err(_) => Err("Invalid timezone".to_string()),
+            Err(_) => Err(DateError::InvalidTimezone(timezone.to_string())),
         }
     }
 
-    /// Creates a `DateX` instance from a string and a format.
-    pub fn parse(date_str: &str, format: &str) -> Result<Self, String> {
-        match DateParser::parse(date_str, format) {
+    /// Creates a `DateX` from a local wall-clock time in a named time zone.
+    ///
+    /// `policy` decides what happens when the local time is skipped or repeated
+    /// by a DST transition.
+    pub fn from_local(
+        naive: NaiveDateTime,
+        timezone: &str,
+        policy: LocalTimePolicy,
+    ) -> Result<Self, DateError> {
+        let datetime = TimeZoneHandler::from_local(naive, timezone, policy)?;
+        Ok(DateX {
+            datetime: datetime.with_timezone(&Utc),
+        })
+    }
+
+    /// Creates a `DateX` instance from a string and a format string or a
+    /// [`WellKnownFormat`](crate::WellKnownFormat).
+    pub fn parse<F: DateFormat>(date_str: &str, format: F) -> Result<Self, DateError> {
+        match format.parse_datetime(date_str) {
             Ok(datetime) => Ok(DateX { datetime }),
             Err(e) => Err(e),
         }
     }
 
+    /// Creates a `DateX` from a string and a format with strict or lenient
+    /// matching and an optional default date.
+    pub fn parse_with_options(
+        date_str: &str,
+        format: &str,
+        options: &ParseOptions,
+    ) -> Result<Self, DateError> {
+        DateParser::compile_with_options(format, options)?.parse(date_str)
+    }
+
+    /// Parses a date string whose month and weekday names are in the given locale,
+    /// e.g. "26 de septiembre de 2023" with "%d de %B de %Y" and "es".
+    pub fn parse_with_locale(
+        date_str: &str,
+        format: &str,
+        locale_code: &str,
+    ) -> Result<Self, DateError> {
+        let locale = LocaleManager::new();
+        DateParser::parse_with_locale(date_str, format, &locale, locale_code)
+            .map(|datetime| DateX { datetime })
+    }
+
+    /// Parses a date in any common format (ISO 8601, RFC 3339, RFC 2822, slash
+    /// dates, YYYYMMDD or a Unix timestamp) and reports which one matched.
+    /// Ambiguous slash dates such as 03/04/2024 are read month-first.
+    pub fn parse_any(input: &str) -> Result<(Self, DetectedFormat), DateError> {
+        Self::parse_any_with_order(input, DateOrder::MonthFirst)
+    }
+
+    /// Like [`DateX::parse_any`], with the given order for ambiguous slash dates.
+    pub fn parse_any_with_order(
+        input: &str,
+        order: DateOrder,
+    ) -> Result<(Self, DetectedFormat), DateError> {
+        FormatDetector::parse_any(input, order)
+            .map(|(datetime, format)| (DateX { datetime }, format))
+    }
+
+    /// Parses a relative English expression such as "tomorrow at 3pm", "next Friday",
+    /// "in 2 weeks" or "last day of the month" against a reference instant.
+    pub fn parse_natural(input: &str, reference: &DateX) -> Result<Self, DateError> {
+        Self::parse_natural_with_locale(input, reference, "en")
+    }
+
+    /// Parses a relative expression using the natural-language words of a locale.
+    pub fn parse_natural_with_locale(
+        input: &str,
+        reference: &DateX,
+        locale_code: &str,
+    ) -> Result<Self, DateError> {
+        NaturalParser::parse(input, reference, &LocaleManager::new(), locale_code)
+    }
+
     /// Creates a `DateX` instance from a timestamp in seconds.
     pub fn from_timestamp(timestamp: i64) -> Self {
-        let datetime = DateTime::<Utc>::from_utc(
-            NaiveDateTime::from_timestamp_opt(timestamp, 0)
-                .unwrap_or_else(|| NaiveDateTime::from_timestamp(0, 0)),
-            Utc,
-        );
+        let datetime = DateTime::<Utc>::from_timestamp(timestamp, 0).unwrap_or_default();
         DateX { datetime }
     }
 
-    /// Formats the date according to the specified format string.
-    pub fn format(&self, format_str: &str) -> String {
-        DateFormatter::format(self, format_str)
+    /// Creates a `DateX` at midnight UTC from an ISO week date, e.g. 2024-W09-3
+    /// is `from_iso_week_date(2024, 9, 3)`. `weekday` runs from Monday = 1 to Sunday = 7.
+    pub fn from_iso_week_date(year: i32, week: u32, weekday: u32) -> Result<Self, DateError> {
+        let weekday = weekday
+            .checked_sub(1)
+            .and_then(|day| Weekday::try_from(day as u8).ok())
+            .ok_or(out_of_range("weekday", weekday))?;
+        let date =
+            NaiveDate::from_isoywd_opt(year, week, weekday).ok_or(out_of_range("week", week))?;
+        Ok(DateX {
+            datetime: date.and_time(NaiveTime::MIN).and_utc(),
+        })
+    }
+
+    /// Creates a `DateX` at midnight UTC from an ordinal date, e.g. 2024-060 is
+    /// `from_ordinal(2024, 60)`.
+    pub fn from_ordinal(year: i32, ordinal: u32) -> Result<Self, DateError> {
+        let date =
+            NaiveDate::from_yo_opt(year, ordinal).ok_or(out_of_range("day of year", ordinal))?;
+        Ok(DateX {
+            datetime: date.and_time(NaiveTime::MIN).and_utc(),
+        })
+    }
+
+    /// Formats the date according to a format string or a [`WellKnownFormat`](crate::WellKnownFormat).
+    pub fn format<F: DateFormat>(&self, format: F) -> String {
+        format.format_datetime(&self.datetime)
+    }
+
+    /// Writes the date using pre-parsed format items, without intermediate allocations.
+    pub fn write_formatted<W: fmt::Write>(&self, w: &mut W, items: &FormatItems) -> fmt::Result {
+        items.write(w, &self.datetime)
     }
 
     /// Returns the year.
@@ -108,12 +205,17 @@ impl DateX {
         self.datetime.timestamp_subsec_millis()
     }
 
-    /// Adds a specified duration to the date.
+    /// Adds a specified duration to the date. Months and years keep the day of
+    /// the month, clamped to the last day of a shorter target month.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the result is outside the supported range of dates.
     pub fn add_duration(&mut self, amount: i64, unit: DurationUnit) {
         self.datetime = TimeManipulator::add_duration(self.datetime, amount, unit);
     }
 
-    /// Subtracts a specified duration from the date.
+    /// Subtracts a specified duration from the date, see [`DateX::add_duration`].
     pub fn subtract_duration(&mut self, amount: i64, unit: DurationUnit) {
         self.datetime = TimeManipulator::subtract_duration(self.datetime, amount, unit);
     }
@@ -143,6 +245,17 @@ impl DateX {
         self.datetime.iso_week().week()
     }
 
+    /// Returns the ISO week-based year, which differs from the calendar year for
+    /// days in the first or last week of a year.
+    pub fn iso_week_year(&self) -> i32 {
+        self.datetime.iso_week().year()
+    }
+
+    /// Returns the day of the year (1-366).
+    pub fn ordinal(&self) -> u32 {
+        self.datetime.ordinal()
+    }
+
     /// Returns the number of days in the month.
     pub fn days_in_month(&self) -> u32 {
         days_in_month(self.year(), self.month())
@@ -169,17 +282,17 @@ impl DateX {
 
     /// Checks if the date is today.
     pub fn is_today(&self) -> bool {
-        self.datetime.date() == Utc::now().date()
+        self.datetime.date_naive() == Utc::now().date_naive()
     }
 
     /// Checks if the date is yesterday.
     pub fn is_yesterday(&self) -> bool {
-        self.datetime.date() == (Utc::now() - chrono::Duration::days(1)).date()
+        self.datetime.date_naive() == (Utc::now() - chrono::Duration::days(1)).date_naive()
     }
 
     /// Checks if the date is tomorrow.
     pub fn is_tomorrow(&self) -> bool {
-        self.datetime.date() == (Utc::now() + chrono::Duration::days(1)).date()
+        self.datetime.date_naive() == (Utc::now() + chrono::Duration::days(1)).date_naive()
     }
 
     /// Checks if the date is a weekend.
@@ -211,7 +324,7 @@ impl DateX {
     }
 
     /// Converts the date to a different time zone.
-    pub fn to_timezone_v(&self, timezone: &str) -> Result<Self, String> {
+    pub fn to_timezone_v(&self, timezone: &str) -> Result<Self, DateError> {
         match get_timezone_offset(timezone) {
             Some(offset) => {
                 let datetime = self.datetime.with_timezone(&offset);
@@ -219,7 +332,7 @@ impl DateX {
                     datetime: datetime.into(),
                 })
             }
-            None => Err("Invalid timezone".to_string()),
+            None => Err(DateError::InvalidTimezone(timezone.to_string())),
         }
     }
 
@@ -257,23 +370,25 @@ impl DateX {
         self.datetime = self.datetime.with_nanosecond(0).unwrap();
     }
 
+    /// Formats the date using the month and weekday names, AM/PM markers and
+    /// ordinals of the given locale. Every `format` token is supported.
     pub fn format_with_locale(&self, format_str: &str, locale_code: &str) -> String {
         let locale_manager = LocaleManager::new();
         locale_manager.format_with_locale(&self.datetime, format_str, locale_code)
     }
 
     /// Gets the offset from UTC for the current time zone.
-    pub fn timezone_offset(&self, timezone: &str) -> Result<chrono::FixedOffset, String> {
+    pub fn timezone_offset(&self, timezone: &str) -> Result<chrono::FixedOffset, DateError> {
         TimeZoneHandler::get_timezone_offset(self.datetime, timezone)
     }
     /// Adds a DurationX to the date.
     pub fn add_durationx(&mut self, durationx: DurationX) {
-        self.datetime = self.datetime + durationx.duration();
+        self.datetime += durationx.duration();
     }
 
     /// Subtracts a DurationX from the date.
     pub fn subtract_durationx(&mut self, durationx: DurationX) {
-        self.datetime = self.datetime - durationx.duration();
+        self.datetime -= durationx.duration();
     }
 
     /// Lists all available time zones.
@@ -354,44 +469,22 @@ impl DateX {
         }
     }
 
-    /// Returns a human-readable relative time string.
+    /// Returns a human-readable relative time string from now, such as "3 days ago"
+    /// for a past date or "in 3 days" for a future one.
     pub fn time_ago(&self) -> String {
-        let now = Utc::now();
-        let diff = now - self.datetime;
-        let seconds = diff.num_seconds();
-        let minutes = diff.num_minutes();
-        let hours = diff.num_hours();
-        let days = diff.num_days();
-
-        if seconds < 60 {
-            format!("{} seconds ago", seconds)
-        } else if minutes < 60 {
-            format!("{} minutes ago", minutes)
-        } else if hours < 24 {
-            format!("{} hours ago", hours)
-        } else {
-            format!("{} days ago", days)
-        }
+        RelativeFormatter::new().format(self)
     }
 
-    /// Returns a human-readable relative time string from now.
+    /// An alias of [`DateX::time_ago`], which already words future dates as
+    /// "in 3 days".
     pub fn from_now(&self) -> String {
-        let now = Utc::now();
-        let diff = self.datetime - now;
-        let seconds = diff.num_seconds();
-        let minutes = diff.num_minutes();
-        let hours = diff.num_hours();
-        let days = diff.num_days();
-
-        if seconds < 60 {
-            format!("in {} seconds", seconds)
-        } else if minutes < 60 {
-            format!("in {} minutes", minutes)
-        } else if hours < 24 {
-            format!("in {} hours", hours)
-        } else {
-            format!("in {} days", days)
-        }
+        self.time_ago()
+    }
+
+    /// Returns a relative time string from now in the given locale, e.g. "hace 2 días"
+    /// or "5 dni temu".
+    pub fn time_ago_with_locale(&self, locale_code: &str) -> String {
+        RelativeFormatter::for_locale(locale_code).format(self)
     }
 
     /// Returns the date as a NaiveDateTime.
@@ -403,11 +496,6 @@ impl DateX {
     pub fn to_datetime(&self) -> DateTime<Utc> {
         self.datetime
     }
-
-    /// Returns the date as a formatted string.
-    pub fn to_string(&self) -> String {
-        self.format("%Y-%m-%d %H:%M:%S")
-    }
 }
 
 impl fmt::Display for DateX {
@@ -436,3 +524,61 @@ impl Sub<chrono::Duration> for DateX {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shifted(start: &str, amount: i64, unit: DurationUnit) -> String {
+        let mut date = DateX::parse(start, "%Y-%m-%d %H:%M").unwrap();
+        date.add_duration(amount, unit);
+        date.format("%Y-%m-%d %H:%M")
+    }
+
+    #[test]
+    fn month_steps_clamp_the_day() {
+        assert_eq!(
+            shifted("2024-01-31 10:00", 1, DurationUnit::Months),
+            "2024-02-29 10:00"
+        );
+        assert_eq!(
+            shifted("2023-01-31 10:00", 1, DurationUnit::Months),
+            "2023-02-28 10:00"
+        );
+        assert_eq!(
+            shifted("2024-03-31 10:00", -1, DurationUnit::Months),
+            "2024-02-29 10:00"
+        );
+        assert_eq!(
+            shifted("2024-01-15 10:00", -13, DurationUnit::Months),
+            "2022-12-15 10:00"
+        );
+        assert_eq!(
+            shifted("2024-02-29 10:00", 1, DurationUnit::Years),
+            "2025-02-28 10:00"
+        );
+        assert_eq!(
+            shifted("2024-02-29 10:00", 4, DurationUnit::Years),
+            "2028-02-29 10:00"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "date out of range")]
+    fn out_of_range_steps_panic() {
+        shifted("2024-01-01 00:00", i64::MAX / 12, DurationUnit::Years);
+    }
+
+    #[test]
+    fn checked_steps_report_out_of_range() {
+        let date = DateX::parse("2024-01-01", "%Y-%m-%d").unwrap().datetime;
+        assert_eq!(
+            TimeManipulator::checked_add_duration(date, i64::MAX, DurationUnit::Days),
+            None
+        );
+        assert_eq!(
+            TimeManipulator::checked_add_duration(date, 300_000, DurationUnit::Years),
+            None
+        );
+    }
+}
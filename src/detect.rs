@@ -0,0 +1,274 @@
+// src/detect.rs
+use crate::error::DateError;
+use crate::parsed::parse_offset;
+use crate::parser::DateParser;
+use chrono::{DateTime, FixedOffset, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use regex::{Captures, Regex};
+use std::fmt;
+use std::sync::LazyLock;
+
+/// How to read a numeric date such as 03/04/2024 when both orders are valid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DateOrder {
+    /// 03/04/2024 is March 4th (US style).
+    #[default]
+    MonthFirst,
+    /// 03/04/2024 is April 3rd (European style).
+    DayFirst,
+}
+
+/// The format [`FormatDetector`] recognised, in catalogue priority order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetectedFormat {
+    /// Compact calendar date, e.g. 20240315.
+    CompactDate,
+    /// Seconds since the Unix epoch, e.g. 1710498600.
+    UnixSeconds,
+    /// Milliseconds since the Unix epoch, e.g. 1710498600000.
+    UnixMillis,
+    /// RFC 3339 timestamp, e.g. 2024-03-15T10:30:00+01:00.
+    Rfc3339,
+    /// Other ISO 8601 date or date-time, e.g. 2024-03-15, 2024-03-15 10:30 or 20240315T103000Z.
+    Iso8601,
+    /// RFC 2822 timestamp, e.g. Fri, 15 Mar 2024 10:30:00 +0000.
+    Rfc2822,
+    /// Month-first slash date, e.g. 03/15/2024.
+    UsDate,
+    /// Day-first slash or dotted date, e.g. 15/03/2024 or 15.03.2024.
+    EuDate,
+}
+
+impl fmt::Display for DetectedFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DetectedFormat::CompactDate => "compact date (YYYYMMDD)",
+            DetectedFormat::UnixSeconds => "Unix seconds",
+            DetectedFormat::UnixMillis => "Unix milliseconds",
+            DetectedFormat::Rfc3339 => "RFC 3339",
+            DetectedFormat::Iso8601 => "ISO 8601",
+            DetectedFormat::Rfc2822 => "RFC 2822",
+            DetectedFormat::UsDate => "US date (MM/DD/YYYY)",
+            DetectedFormat::EuDate => "European date (DD/MM/YYYY)",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The outcome of one catalogue entry: `None` when the input does not have its shape.
+type Detection = Result<(DateTime<Utc>, DetectedFormat), DateError>;
+
+/// Extended and basic ISO 8601 dates with optional time, fraction and offset.
+static ISO8601: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?P<Y>\d{4})(?:-(?P<m>\d{2})-(?P<d>\d{2})|(?P<bm>\d{2})(?P<bd>\d{2}))(?:[T ](?P<H>\d{2})(?::?(?P<M>\d{2})(?::?(?P<S>\d{2})(?:[.,](?P<f>\d{1,9}))?)?)?(?P<z>Z|[+-]\d{2}(?::?\d{2})?)?)?$",
+    )
+    .unwrap()
+});
+
+/// Slash or dotted dates with an optional time.
+static SLASH_DATE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?P<a>\d{1,2})(?P<sep>[/.])(?P<b>\d{1,2})(?P<sep2>[/.])(?P<Y>\d{4})(?:[ T](?P<H>\d{1,2}):(?P<M>\d{2})(?::(?P<S>\d{2}))?)?$",
+    )
+    .unwrap()
+});
+
+/// Parses dates whose format is not known in advance.
+pub struct FormatDetector;
+
+impl FormatDetector {
+    /// Tries each format of the catalogue in priority order and returns the first
+    /// match. Inputs without an offset are read as UTC.
+    pub fn parse_any(
+        input: &str,
+        order: DateOrder,
+    ) -> Result<(DateTime<Utc>, DetectedFormat), DateError> {
+        let input = input.trim();
+        let detectors: [fn(&str, DateOrder) -> Option<Detection>; 5] = [
+            Self::detect_numeric,
+            Self::detect_rfc3339,
+            Self::detect_iso8601,
+            Self::detect_rfc2822,
+            Self::detect_slash_date,
+        ];
+
+        // A recognised shape with an impossible value (e.g. 2024-02-30) is only
+        // reported if no later format accepts the input.
+        let mut first_error = None;
+        for detect in detectors {
+            match detect(input, order) {
+                Some(Ok(found)) => return Ok(found),
+                Some(Err(e)) => {
+                    first_error.get_or_insert(e);
+                }
+                None => {}
+            }
+        }
+        Err(first_error.unwrap_or_else(|| DateError::UnrecognizedFormat(input.to_string())))
+    }
+
+    /// Compact dates and Unix timestamps.
+    fn detect_numeric(input: &str, _order: DateOrder) -> Option<Detection> {
+        let digits = input.strip_prefix('-').unwrap_or(input);
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        // Eight unsigned digits are always a compact date, so 20241340 reports the
+        // month as out of range instead of being read as Unix seconds.
+        if digits.len() == 8 && digits.len() == input.len() {
+            let year = digits[..4].parse().ok()?;
+            let month = digits[4..6].parse().ok()?;
+            let day = digits[6..].parse().ok()?;
+            let result = DateParser::build_date(year, month, day).map(|date| {
+                let datetime = date.and_time(NaiveTime::MIN).and_utc();
+                (datetime, DetectedFormat::CompactDate)
+            });
+            return Some(result);
+        }
+
+        let value: i64 = input.parse().ok()?;
+        let (datetime, format) = if digits.len() >= 12 {
+            (
+                DateTime::from_timestamp_millis(value),
+                DetectedFormat::UnixMillis,
+            )
+        } else {
+            (
+                DateTime::from_timestamp(value, 0),
+                DetectedFormat::UnixSeconds,
+            )
+        };
+        datetime.map(|datetime| Ok((datetime, format)))
+    }
+
+    fn detect_rfc3339(input: &str, _order: DateOrder) -> Option<Detection> {
+        DateTime::parse_from_rfc3339(input)
+            .ok()
+            .map(|datetime| Ok((datetime.with_timezone(&Utc), DetectedFormat::Rfc3339)))
+    }
+
+    /// Extended and basic ISO 8601 dates with optional time, fraction and offset.
+    fn detect_iso8601(input: &str, _order: DateOrder) -> Option<Detection> {
+        let caps = ISO8601.captures(input)?;
+        let number = |name: &str| {
+            caps.name(name)
+                .map_or(0, |m| m.as_str().parse().unwrap_or(0))
+        };
+
+        let month = caps.name("m").or(caps.name("bm"))?.as_str().parse().ok()?;
+        let day = caps.name("d").or(caps.name("bd"))?.as_str().parse().ok()?;
+        let result = DateParser::build_date(number("Y") as i32, month, day).and_then(|date| {
+            let nanos = caps.name("f").map_or(0, |m| {
+                let digits = m.as_str();
+                digits.parse::<u32>().unwrap_or(0) * 10u32.pow(9 - digits.len() as u32)
+            });
+            let time = DateParser::build_time(number("H"), number("M"), number("S"), nanos)?;
+            let naive = NaiveDateTime::new(date, time);
+            let offset = caps.name("z").map_or(Ok(0), |z| parse_offset(z.as_str()))?;
+            let datetime = FixedOffset::east_opt(offset)
+                .and_then(|tz| tz.from_local_datetime(&naive).single())
+                .ok_or(DateError::FieldOutOfRange {
+                    field: "offset",
+                    value: offset as i64,
+                })?;
+            Ok((datetime.with_timezone(&Utc), DetectedFormat::Iso8601))
+        });
+        Some(result)
+    }
+
+    fn detect_rfc2822(input: &str, _order: DateOrder) -> Option<Detection> {
+        DateTime::parse_from_rfc2822(input)
+            .ok()
+            .map(|datetime| Ok((datetime.with_timezone(&Utc), DetectedFormat::Rfc2822)))
+    }
+
+    /// Slash dates (order decided by the values, then by `order`) and dotted
+    /// dates (always day-first), with an optional time.
+    fn detect_slash_date(input: &str, order: DateOrder) -> Option<Detection> {
+        let caps = SLASH_DATE.captures(input)?;
+        if caps["sep"] != caps["sep2"] {
+            return None;
+        }
+        let number = |caps: &Captures, name: &str| {
+            caps.name(name)
+                .map_or(0, |m| m.as_str().parse::<u32>().unwrap_or(0))
+        };
+        let (a, b) = (number(&caps, "a"), number(&caps, "b"));
+
+        let day_first = &caps["sep"] == "." || a > 12 || (b <= 12 && order == DateOrder::DayFirst);
+        let (month, day, format) = if day_first {
+            (b, a, DetectedFormat::EuDate)
+        } else {
+            (a, b, DetectedFormat::UsDate)
+        };
+
+        let result =
+            DateParser::build_date(number(&caps, "Y") as i32, month, day).and_then(|date| {
+                let time = DateParser::build_time(
+                    number(&caps, "H"),
+                    number(&caps, "M"),
+                    number(&caps, "S"),
+                    0,
+                )?;
+                Ok((NaiveDateTime::new(date, time).and_utc(), format))
+            });
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(input: &str) -> Result<(String, DetectedFormat), DateError> {
+        FormatDetector::parse_any(input, DateOrder::MonthFirst)
+            .map(|(datetime, format)| (datetime.format("%Y-%m-%d %H:%M:%S").to_string(), format))
+    }
+
+    #[test]
+    fn compact_dates_with_invalid_fields_are_errors() {
+        assert_eq!(
+            detect("20241340"),
+            Err(DateError::FieldOutOfRange {
+                field: "month",
+                value: 13
+            })
+        );
+        assert_eq!(
+            detect("20240230"),
+            Err(DateError::FieldOutOfRange {
+                field: "day",
+                value: 30
+            })
+        );
+        assert_eq!(
+            detect("20240315").unwrap(),
+            (
+                "2024-03-15 00:00:00".to_string(),
+                DetectedFormat::CompactDate
+            )
+        );
+    }
+
+    #[test]
+    fn other_digit_runs_are_timestamps() {
+        assert_eq!(detect("1710498600").unwrap().1, DetectedFormat::UnixSeconds);
+        assert_eq!(
+            detect("1710498600000").unwrap().1,
+            DetectedFormat::UnixMillis
+        );
+    }
+
+    #[test]
+    fn iso_offsets_are_applied() {
+        assert_eq!(
+            detect("2024-03-15 10:30+05").unwrap(),
+            ("2024-03-15 05:30:00".to_string(), DetectedFormat::Iso8601)
+        );
+        assert_eq!(
+            detect("20240315T103000-0130").unwrap().0,
+            "2024-03-15 12:00:00"
+        );
+    }
+}
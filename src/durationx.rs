@@ -1,4 +1,7 @@
 // src/durationx.rs
+use crate::error::DateError;
+use crate::locale::LocaleManager;
+use crate::manipulator::DurationUnit;
 use chrono::Duration;
 use std::fmt;
 
@@ -10,13 +13,15 @@ pub struct DurationX {
 
 impl DurationX {
     /// Creates a new DurationX from a duration string (e.g., "2 days", "3 hours").
-    pub fn parse(duration_str: &str) -> Result<Self, String> {
-        let parts: Vec<&str> = duration_str.trim().split_whitespace().collect();
+    pub fn parse(duration_str: &str) -> Result<Self, DateError> {
+        let parts: Vec<&str> = duration_str.split_whitespace().collect();
         if parts.len() != 2 {
-            return Err("Invalid duration format".to_string());
+            return Err(DateError::InvalidDurationFormat(duration_str.to_string()));
         }
 
-        let amount = parts[0].parse::<i64>().map_err(|_| "Invalid amount")?;
+        let amount = parts[0]
+            .parse::<i64>()
+            .map_err(|_| DateError::InvalidDurationAmount(parts[0].to_string()))?;
         let unit = parts[1];
 
         let duration = match unit {
@@ -27,7 +32,7 @@ impl DurationX {
             "hours" | "hour" => Duration::hours(amount),
             "minutes" | "minute" => Duration::minutes(amount),
             "seconds" | "second" => Duration::seconds(amount),
-            _ => return Err("Invalid duration unit".to_string()),
+            _ => return Err(DateError::InvalidDurationUnit(unit.to_string())),
         };
 
         Ok(DurationX { duration })
@@ -37,22 +42,26 @@ impl DurationX {
     pub fn duration(&self) -> Duration {
         self.duration
     }
-}
 
-impl fmt::Display for DurationX {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    /// Formats the duration in its largest whole unit (days, hours, minutes or
+    /// seconds) with the plural form of the given locale, e.g. "1 day" or "5 dni".
+    pub fn format_with_locale(&self, locale_code: &str) -> String {
         let secs = self.duration.num_seconds();
-        if secs >= 86400 {
-            let days = secs / 86400;
-            write!(f, "{} days", days)
+        let (amount, unit) = if secs >= 86400 {
+            (secs / 86400, DurationUnit::Days)
         } else if secs >= 3600 {
-            let hours = secs / 3600;
-            write!(f, "{} hours", hours)
+            (secs / 3600, DurationUnit::Hours)
         } else if secs >= 60 {
-            let minutes = secs / 60;
-            write!(f, "{} minutes", minutes)
+            (secs / 60, DurationUnit::Minutes)
         } else {
-            write!(f, "{} seconds", secs)
-        }
+            (secs, DurationUnit::Seconds)
+        };
+        LocaleManager::new().format_unit(locale_code, amount, unit)
+    }
+}
+
+impl fmt::Display for DurationX {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format_with_locale("en"))
     }
 }
@@ -0,0 +1,178 @@
+// src/error.rs
+use crate::formatter::WEEKDAY_NAMES;
+use crate::wellknown::WellKnownFormat;
+use crate::DateX;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Weekday};
+use std::error::Error;
+use std::fmt;
+
+/// The error type returned by every fallible operation in this crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DateError {
+    /// The time zone name is not a known IANA zone.
+    InvalidTimezone(String),
+    /// The input does not match the format; see [`ParseDiagnostic`].
+    PatternMismatch(Box<ParseDiagnostic>),
+    /// A parsed or supplied field is outside its valid range (e.g. month 13, February 30).
+    FieldOutOfRange { field: &'static str, value: i64 },
+    /// The input names a weekday that `date` does not fall on.
+    WeekdayMismatch { date: NaiveDate, weekday: Weekday },
+    /// The local time falls in a DST gap and does not exist in the time zone.
+    NonexistentLocalTime {
+        local: NaiveDateTime,
+        timezone: String,
+    },
+    /// The local time falls in a DST overlap and occurs twice in the time zone.
+    AmbiguousLocalTime {
+        local: NaiveDateTime,
+        timezone: String,
+    },
+    /// A required RRULE part such as FREQ is missing.
+    MissingRulePart(&'static str),
+    /// An RRULE part name is not defined by RFC 5545.
+    UnknownRulePart(String),
+    /// An RRULE part appears more than once.
+    DuplicateRulePart(String),
+    /// An RRULE part has a malformed or out-of-range value.
+    InvalidRulePart { part: String, value: String },
+    /// Two RRULE parts may not be used together (e.g. COUNT and UNTIL).
+    ConflictingRuleParts { first: String, second: String },
+    /// Strict parsing found no value for a required field.
+    MissingField(&'static str),
+    /// Strict parsing found a field that would not affect the result.
+    UnusedField(&'static str),
+    /// The format string itself could not be used.
+    InvalidFormat(String),
+    /// The duration string is not of the form "<amount> <unit>".
+    InvalidDurationFormat(String),
+    /// The amount of a duration string is not an integer.
+    InvalidDurationAmount(String),
+    /// The unit of a duration string is not recognised.
+    InvalidDurationUnit(String),
+    /// The input does not match any format known to `DateX::parse_any`.
+    UnrecognizedFormat(String),
+    /// The input is not in the shape of the requested [`WellKnownFormat`].
+    WellKnownMismatch {
+        format: WellKnownFormat,
+        input: String,
+    },
+    /// A natural-language expression could not be understood; `position` is the
+    /// byte offset of the first unrecognised word.
+    UnrecognizedExpression { input: String, position: usize },
+    /// A natural-language expression has more than one reasonable reading.
+    AmbiguousExpression {
+        input: String,
+        candidates: Vec<DateX>,
+    },
+}
+
+/// Where and why an input stopped matching its format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDiagnostic {
+    pub input: String,
+    pub format: String,
+    /// Byte offset in `input` where matching failed.
+    pub position: usize,
+    /// The format token being matched, such as "%m"; `None` when a literal
+    /// character was expected or the input has text left over.
+    pub token: Option<String>,
+    pub expected: String,
+    pub found: String,
+}
+
+impl fmt::Display for ParseDiagnostic {
+    /// Renders the message followed by the input with a caret under `position`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Date string does not match format '{}' at byte {}: expected {}",
+            self.format, self.position, self.expected
+        )?;
+        if let Some(token) = &self.token {
+            write!(f, " for {}", token)?;
+        }
+        let column = self.input[..self.position].chars().count();
+        write!(
+            f,
+            ", found {}\n  {}\n  {}^",
+            self.found,
+            self.input,
+            " ".repeat(column)
+        )
+    }
+}
+
+impl fmt::Display for DateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DateError::InvalidTimezone(name) => write!(f, "Invalid timezone: '{}'", name),
+            DateError::PatternMismatch(diagnostic) => write!(f, "{}", diagnostic),
+            DateError::FieldOutOfRange { field, value } => {
+                write!(f, "Value {} is out of range for {}", value, field)
+            }
+            DateError::WeekdayMismatch { date, weekday } => write!(
+                f,
+                "{} is a {}, not a {}",
+                date,
+                WEEKDAY_NAMES[date.weekday().num_days_from_monday() as usize],
+                WEEKDAY_NAMES[weekday.num_days_from_monday() as usize]
+            ),
+            DateError::NonexistentLocalTime { local, timezone } => write!(
+                f,
+                "Local time {} does not exist in {} (skipped by a DST transition)",
+                local, timezone
+            ),
+            DateError::AmbiguousLocalTime { local, timezone } => write!(
+                f,
+                "Local time {} is ambiguous in {} (repeated by a DST transition)",
+                local, timezone
+            ),
+            DateError::MissingRulePart(part) => write!(f, "RRULE is missing the {} part", part),
+            DateError::UnknownRulePart(part) => write!(f, "Unknown RRULE part '{}'", part),
+            DateError::DuplicateRulePart(part) => {
+                write!(f, "RRULE part {} is specified more than once", part)
+            }
+            DateError::InvalidRulePart { part, value } => {
+                write!(f, "Invalid value '{}' for RRULE part {}", value, part)
+            }
+            DateError::ConflictingRuleParts { first, second } => {
+                write!(f, "RRULE part {} cannot be combined with {}", first, second)
+            }
+            DateError::MissingField(field) => write!(f, "The input has no {} field", field),
+            DateError::UnusedField(field) => {
+                write!(f, "The {} field has no effect with the other fields", field)
+            }
+            DateError::InvalidFormat(reason) => write!(f, "Invalid format string: {}", reason),
+            DateError::InvalidDurationFormat(input) => {
+                write!(f, "Invalid duration format: '{}'", input)
+            }
+            DateError::InvalidDurationAmount(amount) => {
+                write!(f, "Invalid duration amount: '{}'", amount)
+            }
+            DateError::InvalidDurationUnit(unit) => write!(f, "Invalid duration unit: '{}'", unit),
+            DateError::UnrecognizedFormat(input) => {
+                write!(f, "'{}' does not match any known date format", input)
+            }
+            DateError::WellKnownMismatch { format, input } => {
+                write!(f, "'{}' is not a valid {} date", input, format)
+            }
+            DateError::UnrecognizedExpression { input, position } => write!(
+                f,
+                "Could not understand '{}' at position {}",
+                input, position
+            ),
+            DateError::AmbiguousExpression { input, candidates } => {
+                write!(f, "'{}' is ambiguous; it could mean ", input)?;
+                for (i, candidate) in candidates.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " or ")?;
+                    }
+                    write!(f, "{}", candidate)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for DateError {}
@@ -1,22 +1,56 @@
 // src/formatter.rs
-use crate::utils::get_ordinal_suffix;
+use crate::error::DateError;
+use crate::locale::LocaleManager;
+use crate::utils::ordinal_suffix;
 use crate::DateX;
-use chrono::{DateTime, Datelike, Timelike, Utc};
+use chrono::{DateTime, Datelike, Offset, TimeZone, Timelike, Weekday};
+use std::fmt;
+use std::io;
 
 /// A struct responsible for formatting dates according to format strings.
 pub struct DateFormatter;
 
 impl DateFormatter {
-    /// Formats the `DateX` instance according to the provided format string.
-    pub fn format(datex: &DateX, format_str: &str) -> String {
-        let datetime = datex.datetime;
+    /// Formats a `DateTime` in its own time zone, so local wall-clock fields are used.
+    /// Unknown tokens are copied to the output as-is.
+    pub fn format_datetime<Tz: TimeZone>(datetime: &DateTime<Tz>, format_str: &str) -> String
+    where
+        Tz::Offset: fmt::Display,
+    {
+        Self::format_with_names(datetime, format_str, Names::English)
+    }
+
+    /// Formats a `DateTime` with the month and weekday names, AM/PM markers and
+    /// ordinals of a locale.
+    pub fn format_localized<Tz: TimeZone>(
+        datetime: &DateTime<Tz>,
+        format_str: &str,
+        locale: &LocaleManager,
+        locale_code: &str,
+    ) -> String
+    where
+        Tz::Offset: fmt::Display,
+    {
+        Self::format_with_names(datetime, format_str, Names::Locale(locale, locale_code))
+    }
+
+    fn format_with_names<Tz: TimeZone>(
+        datetime: &DateTime<Tz>,
+        format_str: &str,
+        names: Names<'_>,
+    ) -> String
+    where
+        Tz::Offset: fmt::Display,
+    {
         let mut result = String::new();
         let mut chars = format_str.chars().peekable();
 
         while let Some(c) = chars.next() {
             if c == '%' {
-                if let Some(next_char) = chars.next() {
-                    result.push_str(&Self::format_token(&datetime, next_char, datex));
+                if let (modifiers, Some(token)) = Modifiers::parse(&mut chars) {
+                    // Writing to a String only fails if a single value overflows the
+                    // modifier buffer, which no supported token does.
+                    let _ = modifiers.write_token(&mut result, datetime, token, names);
                 }
             } else {
                 result.push(c);
@@ -26,46 +60,571 @@ impl DateFormatter {
         result
     }
 
-    fn format_token(datetime: &DateTime<Utc>, token: char, datex: &DateX) -> String {
+    /// Writes the value of one token without allocating.
+    fn write_token<W: fmt::Write, Tz: TimeZone>(
+        w: &mut W,
+        datetime: &DateTime<Tz>,
+        token: char,
+        names: Names<'_>,
+    ) -> fmt::Result
+    where
+        Tz::Offset: fmt::Display,
+    {
         match token {
-            'Y' => datetime.year().to_string(),
-            'y' => format!("{:02}", datetime.year() % 100),
-            'm' => format!("{:02}", datetime.month()),
-            'b' => datetime.format("%b").to_string(),
-            'B' => datetime.format("%B").to_string(),
-            'd' => format!("{:02}", datetime.day()),
-            'e' => format!("{}", datetime.day()),
-            'H' => format!("{:02}", datetime.hour()),
-            'I' => format!("{:02}", datetime.hour12().1),
-            'p' => datetime.format("%p").to_string(),
-            'M' => format!("{:02}", datetime.minute()),
-            'S' => format!("{:02}", datetime.second()),
-            'f' => format!("{:06}", datetime.timestamp_subsec_micros()),
-            '3' => format!("{:03}", datetime.timestamp_subsec_millis()),
-            'z' => datetime.format("%z").to_string(),
-            'Z' => datetime.format("%Z").to_string(),
-            'j' => format!("{:03}", datetime.ordinal()),
-            'w' => datetime.format("%w").to_string(),
-            'a' => datetime.format("%a").to_string(),
-            'A' => datetime.format("%A").to_string(),
-            'u' => datetime.weekday().number_from_monday().to_string(),
-            'V' => format!("{:02}", datetime.iso_week().week()),
-            'G' => datetime.iso_week().year().to_string(),
-            'D' => format!("{}{}", datetime.day(), get_ordinal_suffix(datetime.day())),
-            's' => datetime.timestamp().to_string(),
-            'Q' => datex.quarter().to_string(),
-            't' => "\t".to_string(),
-            'n' => "\n".to_string(),
-            '%' => "%".to_string(),
-    
-            _ => token.to_string(),
+            'Y' => write!(w, "{}", datetime.year()),
+            'y' => write!(w, "{:02}", datetime.year() % 100),
+            'm' => write!(w, "{:02}", datetime.month()),
+            'b' => w.write_str(names.month_abbreviation(datetime.month())),
+            'B' => w.write_str(names.month_name(datetime.month())),
+            'd' => write!(w, "{:02}", datetime.day()),
+            'e' => write!(w, "{}", datetime.day()),
+            'H' => write!(w, "{:02}", datetime.hour()),
+            'I' => write!(w, "{:02}", datetime.hour12().1),
+            'p' => w.write_str(names.day_period(datetime.hour12().0)),
+            'M' => write!(w, "{:02}", datetime.minute()),
+            'S' => write!(w, "{:02}", datetime.second()),
+            'f' => write!(w, "{:06}", datetime.timestamp_subsec_micros()),
+            '3' => write!(w, "{:03}", datetime.timestamp_subsec_millis()),
+            'z' => {
+                let seconds = datetime.offset().fix().local_minus_utc();
+                let sign = if seconds < 0 { '-' } else { '+' };
+                // Offsets with seconds (historic local mean time) round to the minute.
+                let minutes = (seconds.abs() + 30) / 60;
+                write!(w, "{}{:02}{:02}", sign, minutes / 60, minutes % 60)
+            }
+            'Z' => write!(w, "{}", datetime.offset()),
+            'j' => write!(w, "{:03}", datetime.ordinal()),
+            'w' => write!(w, "{}", datetime.weekday().num_days_from_sunday()),
+            'a' => w.write_str(names.weekday_abbreviation(datetime.weekday())),
+            'A' => w.write_str(names.weekday_name(datetime.weekday())),
+            'u' => write!(w, "{}", datetime.weekday().number_from_monday()),
+            'V' => write!(w, "{:02}", datetime.iso_week().week()),
+            'G' => write!(w, "{}", datetime.iso_week().year()),
+            'D' => write!(
+                w,
+                "{}{}",
+                datetime.day(),
+                names.ordinal_suffix(datetime.day())
+            ),
+            's' => write!(w, "{}", datetime.timestamp()),
+            'Q' => write!(w, "{}", (datetime.month() - 1) / 3 + 1),
+            't' => w.write_char('\t'),
+            'n' => w.write_char('\n'),
+            _ => w.write_char(token),
         }
     }
 }
 
+/// Every token the formatter understands.
+const KNOWN_TOKENS: &str = "YymbBdeHIpMSf3zZjwaAuVGDsQtn%";
+
+/// Where the formatter takes words from: built-in English tables, or a locale.
+#[derive(Clone, Copy)]
+enum Names<'a> {
+    English,
+    Locale(&'a LocaleManager, &'a str),
+}
+
+impl Names<'_> {
+    fn month_name(self, month: u32) -> &'static str {
+        match self {
+            Names::English => MONTH_NAMES[month as usize - 1],
+            Names::Locale(locale, code) => locale.month_name(code, month),
+        }
+    }
+
+    fn month_abbreviation(self, month: u32) -> &'static str {
+        match self {
+            Names::English => &MONTH_NAMES[month as usize - 1][..3],
+            Names::Locale(locale, code) => locale.month_abbreviation(code, month),
+        }
+    }
+
+    fn weekday_name(self, weekday: Weekday) -> &'static str {
+        match self {
+            Names::English => WEEKDAY_NAMES[weekday.num_days_from_monday() as usize],
+            Names::Locale(locale, code) => locale.weekday_name(code, weekday),
+        }
+    }
+
+    fn weekday_abbreviation(self, weekday: Weekday) -> &'static str {
+        match self {
+            Names::English => &WEEKDAY_NAMES[weekday.num_days_from_monday() as usize][..3],
+            Names::Locale(locale, code) => locale.weekday_abbreviation(code, weekday),
+        }
+    }
+
+    fn day_period(self, is_pm: bool) -> &'static str {
+        match self {
+            Names::English => ["AM", "PM"][is_pm as usize],
+            Names::Locale(locale, code) => locale.day_period(code, is_pm),
+        }
+    }
+
+    fn ordinal_suffix(self, number: u32) -> &'static str {
+        match self {
+            Names::English => ordinal_suffix(number),
+            Names::Locale(locale, code) => locale.ordinal_suffix(code, number),
+        }
+    }
+}
+
+pub(crate) const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+pub(crate) const WEEKDAY_NAMES: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+/// A format string parsed once into literals and tokens, for formatting many dates
+/// straight into a writer.
+///
+/// Unknown tokens and a trailing '%' are rejected by [`FormatItems::parse`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatItems {
+    items: Vec<FormatItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum FormatItem {
+    Literal(String),
+    Token(char, Modifiers),
+}
+
+impl FormatItems {
+    /// Parses and validates a format string.
+    pub fn parse(format_str: &str) -> Result<Self, DateError> {
+        let mut items = Vec::new();
+        let mut literal = String::new();
+        let mut chars = format_str.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                literal.push(c);
+                continue;
+            }
+            let token = match Modifiers::parse(&mut chars) {
+                (_, None) => {
+                    return Err(DateError::InvalidFormat("format ends with '%'".to_string()))
+                }
+                (_, Some(token)) if !KNOWN_TOKENS.contains(token) => {
+                    return Err(DateError::InvalidFormat(format!(
+                        "unknown token %{}",
+                        token
+                    )))
+                }
+                (modifiers, Some(token)) => (modifiers, token),
+            };
+            match token {
+                (modifiers, '%') if modifiers == Modifiers::default() => literal.push('%'),
+                (modifiers, token) => {
+                    if !literal.is_empty() {
+                        items.push(FormatItem::Literal(std::mem::take(&mut literal)));
+                    }
+                    items.push(FormatItem::Token(token, modifiers));
+                }
+            }
+        }
+        if !literal.is_empty() {
+            items.push(FormatItem::Literal(literal));
+        }
+
+        Ok(FormatItems { items })
+    }
+
+    /// Writes a date in its own time zone into any `fmt::Write`.
+    pub fn write<W: fmt::Write, Tz: TimeZone>(
+        &self,
+        w: &mut W,
+        datetime: &DateTime<Tz>,
+    ) -> fmt::Result
+    where
+        Tz::Offset: fmt::Display,
+    {
+        self.write_with_names(w, datetime, Names::English)
+    }
+
+    /// Writes a date into any `fmt::Write` using the words of a locale.
+    pub fn write_with_locale<W: fmt::Write, Tz: TimeZone>(
+        &self,
+        w: &mut W,
+        datetime: &DateTime<Tz>,
+        locale: &LocaleManager,
+        locale_code: &str,
+    ) -> fmt::Result
+    where
+        Tz::Offset: fmt::Display,
+    {
+        self.write_with_names(w, datetime, Names::Locale(locale, locale_code))
+    }
+
+    fn write_with_names<W: fmt::Write, Tz: TimeZone>(
+        &self,
+        w: &mut W,
+        datetime: &DateTime<Tz>,
+        names: Names<'_>,
+    ) -> fmt::Result
+    where
+        Tz::Offset: fmt::Display,
+    {
+        for item in &self.items {
+            match item {
+                FormatItem::Literal(text) => w.write_str(text)?,
+                FormatItem::Token(token, modifiers) => {
+                    modifiers.write_token(w, datetime, *token, names)?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a date in its own time zone into any `io::Write`.
+    pub fn write_io<W: io::Write, Tz: TimeZone>(
+        &self,
+        w: &mut W,
+        datetime: &DateTime<Tz>,
+    ) -> io::Result<()>
+    where
+        Tz::Offset: fmt::Display,
+    {
+        let mut adapter = IoAdapter {
+            inner: w,
+            error: None,
+        };
+        match self.write(&mut adapter, datetime) {
+            Ok(()) => Ok(()),
+            Err(_) => Err(adapter
+                .error
+                .unwrap_or_else(|| io::Error::other("formatting failed"))),
+        }
+    }
+
+    /// Formats a date into a new `String`.
+    pub fn format<Tz: TimeZone>(&self, datetime: &DateTime<Tz>) -> String
+    where
+        Tz::Offset: fmt::Display,
+    {
+        let mut result = String::new();
+        let _ = self.write(&mut result, datetime);
+        result
+    }
+}
+
+/// Forwards `fmt::Write` calls to an `io::Write`, keeping the first I/O error.
+struct IoAdapter<'a, W: io::Write> {
+    inner: &'a mut W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> fmt::Write for IoAdapter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+/// A fixed-size stack buffer used to apply modifiers to a token's value.
+struct TokenBuffer {
+    bytes: [u8; 64],
+    len: usize,
+}
+
+impl TokenBuffer {
+    fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.bytes[..self.len]).unwrap_or("")
+    }
+}
+
+impl fmt::Write for TokenBuffer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        if end > self.bytes.len() {
+            return Err(fmt::Error);
+        }
+        self.bytes[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// How a token's value is padded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Padding {
+    /// Zeros for numbers, spaces for text.
+    #[default]
+    Default,
+    /// `-`: strip the token's own padding and never pad to a width.
+    None,
+    /// `_`: pad with spaces.
+    Space,
+    /// `0`: pad with zeros.
+    Zero,
+}
+
+/// The GNU-style flags and width between '%' and a token, e.g. `%-d`, `%_m`,
+/// `%^B`, `%#Z` or `%10B`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Modifiers {
+    padding: Padding,
+    uppercase: bool,
+    swap_case: bool,
+    width: Option<usize>,
+}
+
+impl Modifiers {
+    /// Reads flags, a width and the token that follows them. The token is `None`
+    /// when the format ends first.
+    fn parse(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> (Self, Option<char>) {
+        let mut modifiers = Modifiers::default();
+        while let Some(&c) = chars.peek() {
+            match c {
+                '-' => modifiers.padding = Padding::None,
+                '_' => modifiers.padding = Padding::Space,
+                '0' => modifiers.padding = Padding::Zero,
+                '^' => modifiers.uppercase = true,
+                '#' => modifiers.swap_case = true,
+                _ => break,
+            }
+            chars.next();
+        }
+        let mut last_digit = None;
+        while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+            if let Some(previous) = last_digit {
+                let width = modifiers.width.unwrap_or(0);
+                modifiers.width = Some(width.saturating_mul(10).saturating_add(previous));
+            }
+            last_digit = Some(digit as usize);
+            chars.next();
+        }
+        let next = chars.peek().copied();
+        match last_digit {
+            // "%3" is the milliseconds token, unless a real token follows the digits.
+            Some(3) if !next.is_some_and(|c| c != '%' && KNOWN_TOKENS.contains(c)) => {
+                (modifiers, Some('3'))
+            }
+            Some(digit) => {
+                let width = modifiers.width.unwrap_or(0);
+                modifiers.width = Some(width.saturating_mul(10).saturating_add(digit));
+                (modifiers, chars.next())
+            }
+            None => (modifiers, chars.next()),
+        }
+    }
+
+    /// Writes a token's value with the flags applied.
+    fn write_token<W: fmt::Write, Tz: TimeZone>(
+        &self,
+        w: &mut W,
+        datetime: &DateTime<Tz>,
+        token: char,
+        names: Names<'_>,
+    ) -> fmt::Result
+    where
+        Tz::Offset: fmt::Display,
+    {
+        if *self == Modifiers::default() {
+            return DateFormatter::write_token(w, datetime, token, names);
+        }
+        let mut buffer = TokenBuffer {
+            bytes: [0; 64],
+            len: 0,
+        };
+        DateFormatter::write_token(&mut buffer, datetime, token, names)?;
+        self.write(w, token, buffer.as_str())
+    }
+
+    /// Applies the flags to a token's formatted value.
+    fn write<W: fmt::Write>(&self, w: &mut W, token: char, value: &str) -> fmt::Result {
+        let numeric = NUMERIC_TOKENS.contains(token);
+
+        // Leading zeros added by the token itself (e.g. "05" for %d).
+        let mut leading_spaces = 0;
+        let mut value = value;
+        if numeric && PADDED_TOKENS.contains(token) && self.padding != Padding::Zero {
+            let digits = value.trim_start_matches('0');
+            let digits = if digits.is_empty() { "0" } else { digits };
+            match self.padding {
+                Padding::None => value = digits,
+                Padding::Space => {
+                    leading_spaces = value.len() - digits.len();
+                    value = digits;
+                }
+                _ => {}
+            }
+        }
+
+        // A padding flag alone pads two-wide tokens such as %e to their width.
+        let width = self.width.or_else(|| {
+            let flagged = matches!(self.padding, Padding::Space | Padding::Zero);
+            (flagged && TWO_WIDE_TOKENS.contains(token)).then_some(2)
+        });
+        let len = leading_spaces + value.chars().count();
+        if let Some(width) = width.filter(|_| self.padding != Padding::None) {
+            let pad = match self.padding {
+                Padding::Space => ' ',
+                Padding::Zero => '0',
+                _ if numeric => '0',
+                _ => ' ',
+            };
+            for _ in len..width {
+                w.write_char(pad)?;
+            }
+        }
+        for _ in 0..leading_spaces {
+            w.write_char(' ')?;
+        }
+
+        for c in value.chars() {
+            if self.uppercase || (self.swap_case && c.is_lowercase()) {
+                for upper in c.to_uppercase() {
+                    w.write_char(upper)?;
+                }
+            } else if self.swap_case && c.is_uppercase() {
+                for lower in c.to_lowercase() {
+                    w.write_char(lower)?;
+                }
+            } else {
+                w.write_char(c)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Tokens whose value is a number, padded with zeros to a width by default.
+const NUMERIC_TOKENS: &str = "YymdeHIMSf3jwuVGsQ";
+
+/// Numeric tokens that the formatter pads with leading zeros on its own.
+const PADDED_TOKENS: &str = "ymdHIMSjV";
+
+/// Numeric tokens up to two digits wide that the formatter leaves unpadded.
+const TWO_WIDE_TOKENS: &str = "e";
+
 impl DateX {
     /// Returns the quarter of the year (1-4).
     pub fn quarter(&self) -> u32 {
         (self.month() - 1) / 3 + 1
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, Utc};
+
+    fn date() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 4, 9, 5, 7).unwrap()
+    }
+
+    fn format(format_str: &str) -> String {
+        DateFormatter::format_datetime(&date(), format_str)
+    }
+
+    #[test]
+    fn padding_flags() {
+        assert_eq!(format("%-d/%-m %-H:%-M"), "4/3 9:5");
+        assert_eq!(format("%_d/%_m"), " 4/ 3");
+        assert_eq!(format("%0e|%_e|%-e|%e"), "04| 4|4|4");
+        assert_eq!(format("%5d|%_5d|%-5d"), "00004|    4|4");
+        assert_eq!(format("%_3j|%-j"), " 64|64");
+    }
+
+    #[test]
+    fn case_flags() {
+        assert_eq!(format("%^B %^a"), "MARCH MON");
+        assert_eq!(format("%#Z %#p"), "utc am");
+        assert_eq!(format("%^#b"), "MAR");
+    }
+
+    #[test]
+    fn widths() {
+        assert_eq!(format("[%10B]"), "[     March]");
+        assert_eq!(format("[%010B]"), "[00000March]");
+        assert_eq!(format("[%-10B]"), "[March]");
+        assert_eq!(format("[%3B]"), "[March]");
+    }
+
+    #[test]
+    fn millisecond_token_and_width_three() {
+        let date = Utc
+            .with_ymd_and_hms(2024, 3, 4, 9, 5, 7)
+            .unwrap()
+            .with_nanosecond(42_000_000)
+            .unwrap();
+        assert_eq!(DateFormatter::format_datetime(&date, "%S.%3"), "07.042");
+        assert_eq!(DateFormatter::format_datetime(&date, "%3d"), "004");
+        assert_eq!(DateFormatter::format_datetime(&date, "%3%%"), "042%");
+        assert_eq!(
+            DateFormatter::format_datetime(&date, "%13d"),
+            "0000000000004"
+        );
+    }
+
+    #[test]
+    fn format_items_match_format_datetime() {
+        let formats = ["%Y-%m-%d %H:%M:%S", "%-d %^B %Y", "%e %3 %% %10A", "day %j"];
+        for format_str in formats {
+            let items = FormatItems::parse(format_str).unwrap();
+            assert_eq!(items.format(&date()), format(format_str), "{}", format_str);
+        }
+        let offset = FixedOffset::east_opt(5 * 3600 + 30 * 60).unwrap();
+        let local = date().with_timezone(&offset);
+        let items = FormatItems::parse("%H:%M %z").unwrap();
+        assert_eq!(items.format(&local), "14:35 +0530");
+    }
+
+    #[test]
+    fn format_items_reject_bad_formats() {
+        assert!(matches!(
+            FormatItems::parse("%Y %k"),
+            Err(DateError::InvalidFormat(_))
+        ));
+        assert!(matches!(
+            FormatItems::parse("%Y %"),
+            Err(DateError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn write_io_writes_bytes_and_reports_errors() {
+        let items = FormatItems::parse("%A %-d %B").unwrap();
+        let mut bytes = Vec::new();
+        items.write_io(&mut bytes, &date()).unwrap();
+        assert_eq!(bytes, b"Monday 4 March");
+
+        let mut full = [0u8; 4];
+        let error = items.write_io(&mut &mut full[..], &date()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::WriteZero);
+    }
+
+    #[test]
+    fn write_with_locale_uses_locale_words() {
+        let locale = LocaleManager::new();
+        let items = FormatItems::parse("%A %D %B").unwrap();
+        let mut text = String::new();
+        items
+            .write_with_locale(&mut text, &date(), &locale, "es")
+            .unwrap();
+        assert_eq!(text, "Lunes 4º Marzo");
+    }
+}
@@ -3,14 +3,32 @@
 //! This crate provides extensive functionality similar to popular date libraries, with added features and Rust-specific optimizations.
 
 mod datex;
+mod detect;
 mod durationx;
+mod error;
 mod formatter;
 mod locale;
 pub mod macros;
 mod manipulator;
+mod natural;
+mod parsed;
 mod parser;
+mod plural;
 pub mod recurrence;
+mod relative;
 pub mod timezone;
 pub mod utils;
+mod wellknown;
+mod zoned;
 pub use crate::datex::DateX;
+pub use detect::{DateOrder, DetectedFormat};
+pub use durationx::DurationX;
+pub use error::{DateError, ParseDiagnostic};
+pub use formatter::FormatItems;
 pub use manipulator::DurationUnit;
+pub use parsed::{Parsed, YearMonth};
+pub use parser::{CompiledFormat, DateParser, ParseMode, ParseOptions};
+pub use plural::PluralCategory;
+pub use relative::{RelativeFormatter, RelativeNumeric, RelativeStyle, RelativeThresholds};
+pub use wellknown::{DateFormat, WellKnownFormat};
+pub use zoned::ZonedDateX;
@@ -1,17 +1,45 @@
 // src/locale.rs
-use chrono::Timelike;
-use chrono::{DateTime, Datelike, Utc};
+use crate::formatter::DateFormatter;
+use crate::manipulator::DurationUnit;
+use crate::natural::{NaturalLexicon, ENGLISH_LEXICON};
+use crate::plural::{
+    self,
+    PluralCategory::{self, Few, Many, One, Other},
+    PluralForms,
+};
+use crate::relative::{
+    RelativeWords, ENGLISH_RELATIVE_WORDS, POLISH_RELATIVE_WORDS, SPANISH_RELATIVE_WORDS,
+};
+use crate::utils::ordinal_suffix;
+use chrono::{DateTime, Utc, Weekday};
 use std::collections::HashMap;
+#[derive(Clone)]
 pub struct LocaleManager {
     month_names: HashMap<String, Vec<&'static str>>,
+    month_abbreviations: HashMap<String, Vec<&'static str>>,
     weekday_names: HashMap<String, Vec<&'static str>>,
+    weekday_abbreviations: HashMap<String, Vec<&'static str>>,
+    day_periods: HashMap<String, Vec<&'static str>>,
+    ordinal_suffixes: HashMap<String, fn(u32) -> &'static str>,
+    natural_lexicons: HashMap<String, NaturalLexicon>,
+    relative_words: HashMap<String, RelativeWords>,
+    plural_rules: HashMap<String, fn(u64) -> PluralCategory>,
+    unit_names: HashMap<String, Vec<(DurationUnit, PluralForms)>>,
 }
 
 impl LocaleManager {
     /// Creates a new LocaleManager with default locales.
     pub fn new() -> Self {
         let mut month_names = HashMap::new();
+        let mut month_abbreviations = HashMap::new();
         let mut weekday_names = HashMap::new();
+        let mut weekday_abbreviations = HashMap::new();
+        let mut day_periods = HashMap::new();
+        let mut ordinal_suffixes: HashMap<String, fn(u32) -> &'static str> = HashMap::new();
+        let mut natural_lexicons = HashMap::new();
+        let mut relative_words = HashMap::new();
+        let mut plural_rules: HashMap<String, fn(u64) -> PluralCategory> = HashMap::new();
+        let mut unit_names = HashMap::new();
 
         month_names.insert(
             "en".to_string(),
@@ -31,6 +59,13 @@ impl LocaleManager {
             ],
         );
 
+        month_abbreviations.insert(
+            "en".to_string(),
+            vec![
+                "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+            ],
+        );
+
         weekday_names.insert(
             "en".to_string(),
             vec![
@@ -44,6 +79,49 @@ impl LocaleManager {
             ],
         );
 
+        weekday_abbreviations.insert(
+            "en".to_string(),
+            vec!["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"],
+        );
+
+        day_periods.insert("en".to_string(), vec!["AM", "PM"]);
+        ordinal_suffixes.insert("en".to_string(), ordinal_suffix);
+
+        natural_lexicons.insert("en".to_string(), ENGLISH_LEXICON);
+        relative_words.insert("en".to_string(), ENGLISH_RELATIVE_WORDS);
+        plural_rules.insert("en".to_string(), plural::english);
+
+        unit_names.insert(
+            "en".to_string(),
+            vec![
+                (
+                    DurationUnit::Seconds,
+                    &[(One, "{} second"), (Other, "{} seconds")] as PluralForms,
+                ),
+                (
+                    DurationUnit::Minutes,
+                    &[(One, "{} minute"), (Other, "{} minutes")],
+                ),
+                (
+                    DurationUnit::Hours,
+                    &[(One, "{} hour"), (Other, "{} hours")],
+                ),
+                (DurationUnit::Days, &[(One, "{} day"), (Other, "{} days")]),
+                (
+                    DurationUnit::Weeks,
+                    &[(One, "{} week"), (Other, "{} weeks")],
+                ),
+                (
+                    DurationUnit::Months,
+                    &[(One, "{} month"), (Other, "{} months")],
+                ),
+                (
+                    DurationUnit::Years,
+                    &[(One, "{} year"), (Other, "{} years")],
+                ),
+            ],
+        );
+
         // Example for Spanish
         month_names.insert(
             "es".to_string(),
@@ -63,6 +141,13 @@ impl LocaleManager {
             ],
         );
 
+        month_abbreviations.insert(
+            "es".to_string(),
+            vec![
+                "Ene", "Feb", "Mar", "Abr", "May", "Jun", "Jul", "Ago", "Sep", "Oct", "Nov", "Dic",
+            ],
+        );
+
         weekday_names.insert(
             "es".to_string(),
             vec![
@@ -76,41 +161,275 @@ impl LocaleManager {
             ],
         );
 
+        weekday_abbreviations.insert(
+            "es".to_string(),
+            vec!["Lun", "Mar", "Mié", "Jue", "Vie", "Sáb", "Dom"],
+        );
+
+        day_periods.insert("es".to_string(), vec!["a. m.", "p. m."]);
+        ordinal_suffixes.insert("es".to_string(), |_| "º");
+        relative_words.insert("es".to_string(), SPANISH_RELATIVE_WORDS);
+        plural_rules.insert("es".to_string(), plural::spanish);
+
+        unit_names.insert(
+            "es".to_string(),
+            vec![
+                (
+                    DurationUnit::Seconds,
+                    &[
+                        (One, "{} segundo"),
+                        (Many, "{} de segundos"),
+                        (Other, "{} segundos"),
+                    ] as PluralForms,
+                ),
+                (
+                    DurationUnit::Minutes,
+                    &[
+                        (One, "{} minuto"),
+                        (Many, "{} de minutos"),
+                        (Other, "{} minutos"),
+                    ],
+                ),
+                (
+                    DurationUnit::Hours,
+                    &[(One, "{} hora"), (Many, "{} de horas"), (Other, "{} horas")],
+                ),
+                (
+                    DurationUnit::Days,
+                    &[(One, "{} día"), (Many, "{} de días"), (Other, "{} días")],
+                ),
+                (
+                    DurationUnit::Weeks,
+                    &[
+                        (One, "{} semana"),
+                        (Many, "{} de semanas"),
+                        (Other, "{} semanas"),
+                    ],
+                ),
+                (
+                    DurationUnit::Months,
+                    &[(One, "{} mes"), (Many, "{} de meses"), (Other, "{} meses")],
+                ),
+                (
+                    DurationUnit::Years,
+                    &[(One, "{} año"), (Many, "{} de años"), (Other, "{} años")],
+                ),
+            ],
+        );
+
+        // Polish only has plural rules and unit words; names fall back to English.
+        relative_words.insert("pl".to_string(), POLISH_RELATIVE_WORDS);
+        plural_rules.insert("pl".to_string(), plural::polish);
+
+        unit_names.insert(
+            "pl".to_string(),
+            vec![
+                (
+                    DurationUnit::Seconds,
+                    &[
+                        (One, "{} sekunda"),
+                        (Few, "{} sekundy"),
+                        (Many, "{} sekund"),
+                        (Other, "{} sekundy"),
+                    ] as PluralForms,
+                ),
+                (
+                    DurationUnit::Minutes,
+                    &[
+                        (One, "{} minuta"),
+                        (Few, "{} minuty"),
+                        (Many, "{} minut"),
+                        (Other, "{} minuty"),
+                    ],
+                ),
+                (
+                    DurationUnit::Hours,
+                    &[
+                        (One, "{} godzina"),
+                        (Few, "{} godziny"),
+                        (Many, "{} godzin"),
+                        (Other, "{} godziny"),
+                    ],
+                ),
+                (
+                    DurationUnit::Days,
+                    &[
+                        (One, "{} dzień"),
+                        (Few, "{} dni"),
+                        (Many, "{} dni"),
+                        (Other, "{} dnia"),
+                    ],
+                ),
+                (
+                    DurationUnit::Weeks,
+                    &[
+                        (One, "{} tydzień"),
+                        (Few, "{} tygodnie"),
+                        (Many, "{} tygodni"),
+                        (Other, "{} tygodnia"),
+                    ],
+                ),
+                (
+                    DurationUnit::Months,
+                    &[
+                        (One, "{} miesiąc"),
+                        (Few, "{} miesiące"),
+                        (Many, "{} miesięcy"),
+                        (Other, "{} miesiąca"),
+                    ],
+                ),
+                (
+                    DurationUnit::Years,
+                    &[
+                        (One, "{} rok"),
+                        (Few, "{} lata"),
+                        (Many, "{} lat"),
+                        (Other, "{} roku"),
+                    ],
+                ),
+            ],
+        );
+
         LocaleManager {
             month_names,
+            month_abbreviations,
             weekday_names,
+            weekday_abbreviations,
+            day_periods,
+            ordinal_suffixes,
+            natural_lexicons,
+            relative_words,
+            plural_rules,
+            unit_names,
         }
     }
 
-    /// Formats a datetime with the specified locale.
+    /// Returns the full name of the month (1-12), falling back to English.
+    pub fn month_name(&self, locale_code: &str, month: u32) -> &'static str {
+        Self::lookup(&self.month_names, locale_code)[month as usize - 1]
+    }
+
+    /// Returns the abbreviated name of the month (1-12), falling back to English.
+    pub fn month_abbreviation(&self, locale_code: &str, month: u32) -> &'static str {
+        Self::lookup(&self.month_abbreviations, locale_code)[month as usize - 1]
+    }
+
+    /// Returns the full name of the weekday, falling back to English.
+    pub fn weekday_name(&self, locale_code: &str, weekday: Weekday) -> &'static str {
+        Self::lookup(&self.weekday_names, locale_code)[weekday.num_days_from_monday() as usize]
+    }
+
+    /// Returns the abbreviated name of the weekday, falling back to English.
+    pub fn weekday_abbreviation(&self, locale_code: &str, weekday: Weekday) -> &'static str {
+        Self::lookup(&self.weekday_abbreviations, locale_code)
+            [weekday.num_days_from_monday() as usize]
+    }
+
+    /// Returns the AM or PM marker, falling back to English.
+    pub fn day_period(&self, locale_code: &str, is_pm: bool) -> &'static str {
+        Self::lookup(&self.day_periods, locale_code)[is_pm as usize]
+    }
+
+    /// Returns the suffix that makes `number` an ordinal (e.g. "st" in "1st"),
+    /// falling back to English.
+    pub fn ordinal_suffix(&self, locale_code: &str, number: u32) -> &'static str {
+        let suffix = self
+            .ordinal_suffixes
+            .get(locale_code)
+            .unwrap_or(&self.ordinal_suffixes["en"]);
+        suffix(number)
+    }
+
+    /// Finds the month (1-12) whose full or abbreviated name matches `text`,
+    /// ignoring case and accents.
+    pub fn parse_month(&self, locale_code: &str, text: &str) -> Option<u32> {
+        let text = fold(text);
+        let names = Self::lookup(&self.month_names, locale_code);
+        let abbreviations = Self::lookup(&self.month_abbreviations, locale_code);
+        (0..12)
+            .find(|&i| fold(names[i]) == text || fold(abbreviations[i]) == text)
+            .map(|i| i as u32 + 1)
+    }
+
+    /// Finds the weekday whose full or abbreviated name matches `text`, ignoring
+    /// case and accents.
+    pub fn parse_weekday(&self, locale_code: &str, text: &str) -> Option<Weekday> {
+        let text = fold(text);
+        let names = Self::lookup(&self.weekday_names, locale_code);
+        let abbreviations = Self::lookup(&self.weekday_abbreviations, locale_code);
+        (0..7)
+            .find(|&i| fold(names[i]) == text || fold(abbreviations[i]) == text)
+            .and_then(|i| Weekday::try_from(i as u8).ok())
+    }
+
+    /// Returns the words used to parse natural-language dates, falling back to English.
+    pub fn natural_lexicon(&self, locale_code: &str) -> &NaturalLexicon {
+        self.natural_lexicons
+            .get(locale_code)
+            .unwrap_or(&self.natural_lexicons["en"])
+    }
+
+    /// Returns the words used to describe relative times, falling back to English.
+    pub fn relative_words(&self, locale_code: &str) -> &RelativeWords {
+        self.relative_words
+            .get(locale_code)
+            .unwrap_or(&self.relative_words["en"])
+    }
+
+    /// Returns the CLDR plural category of a whole number, using English rules for
+    /// unknown locales. The sign is ignored.
+    pub fn plural_category(&self, locale_code: &str, count: i64) -> PluralCategory {
+        let rule = self
+            .plural_rules
+            .get(locale_code)
+            .unwrap_or(&self.plural_rules["en"]);
+        rule(count.unsigned_abs())
+    }
+
+    /// Formats an amount of a unit with the correct plural form, e.g. "1 day",
+    /// "2 dni" or "5 dni", falling back to English words.
+    pub fn format_unit(&self, locale_code: &str, amount: i64, unit: DurationUnit) -> String {
+        let category = self.plural_category(locale_code, amount);
+        let forms = [locale_code, "en"]
+            .iter()
+            .filter_map(|code| self.unit_names.get(*code))
+            .find_map(|names| names.iter().find(|(u, _)| *u == unit))
+            .map_or(&[] as PluralForms, |(_, forms)| *forms);
+        plural::select(forms, category).replace("{}", &amount.to_string())
+    }
+
+    fn lookup<'a>(
+        table: &'a HashMap<String, Vec<&'static str>>,
+        locale_code: &str,
+    ) -> &'a [&'static str] {
+        table.get(locale_code).unwrap_or(&table["en"])
+    }
+
+    /// Formats a datetime with the specified locale. Every formatter token is
+    /// supported; names, AM/PM markers and ordinals come from the locale.
     pub fn format_with_locale(
         &self,
         datetime: &DateTime<Utc>,
         format_str: &str,
         locale_code: &str,
     ) -> String {
-        let months = self
-            .month_names
-            .get(locale_code)
-            .unwrap_or(&self.month_names["en"]);
-        let weekdays = self
-            .weekday_names
-            .get(locale_code)
-            .unwrap_or(&self.weekday_names["en"]);
-
-        let mut output = format_str.to_string();
-        output = output.replace("%B", months[(datetime.month0()) as usize]);
-        output = output.replace(
-            "%A",
-            weekdays[(datetime.weekday().num_days_from_monday()) as usize],
-        );
-        output = output.replace("%Y", &datetime.year().to_string());
-        output = output.replace("%m", &format!("{:02}", datetime.month()));
-        output = output.replace("%d", &format!("{:02}", datetime.day()));
-        output = output.replace("%H", &format!("{:02}", datetime.hour()));
-        output = output.replace("%M", &format!("{:02}", datetime.minute()));
-        output = output.replace("%S", &format!("{:02}", datetime.second()));
-
-        output
+        DateFormatter::format_localized(datetime, format_str, self, locale_code)
     }
 }
+
+/// Lowercases `text` and strips the accents of Latin letters, so "Sábado" matches "sabado".
+fn fold(text: &str) -> String {
+    text.chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| match c {
+            'á' | 'à' | 'â' | 'ä' | 'ã' | 'å' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'í' | 'ì' | 'î' | 'ï' => 'i',
+            'ó' | 'ò' | 'ô' | 'ö' | 'õ' => 'o',
+            'ú' | 'ù' | 'û' | 'ü' => 'u',
+            'ñ' => 'n',
+            'ç' => 'c',
+            _ => c,
+        })
+        .collect()
+}
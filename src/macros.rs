@@ -7,7 +7,7 @@ macro_rules! datex {
             Some(naive_date) => {
                 let naive_datetime = naive_date.and_hms_opt(0, 0, 0).unwrap();
                 $crate::DateX {
-                    datetime: chrono::DateTime::<Utc>::from_utc(naive_datetime, Utc),
+                    datetime: chrono::DateTime::<Utc>::from_naive_utc_and_offset(naive_datetime, Utc),
                 }
             },
             None => panic!(
@@ -22,7 +22,7 @@ macro_rules! datex {
             Some(naive_date) => {
                 match naive_date.and_hms_opt($hour, $minute, $second) {
                     Some(naive_datetime) => $crate::DateX {
-                        datetime: chrono::DateTime::<Utc>::from_utc(naive_datetime, Utc),
+                        datetime: chrono::DateTime::<Utc>::from_naive_utc_and_offset(naive_datetime, Utc),
                     },
                     None => panic!(
                         "Invalid time components in datex! macro: hour={}, minute={}, second={}",
@@ -39,7 +39,6 @@ macro_rules! datex {
 }
 
 /// Macro to parse a date string using a format.
-
 #[macro_export]
 macro_rules! parse_datex {
     ($date_str:expr, $format:expr) => {{
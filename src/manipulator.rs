@@ -1,7 +1,8 @@
 // src/manipulator.rs
+use crate::utils::days_in_month;
 use chrono::Datelike;
 use chrono::Timelike;
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 
 /// Enumeration of duration units for time manipulation.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -22,31 +23,51 @@ pub enum DurationUnit {
 pub struct TimeManipulator;
 
 impl TimeManipulator {
-    /// Adds a duration to the given DateTime<Utc>.
+    /// Adds a duration to the given DateTime<Utc>. Adding months or years keeps
+    /// the day of the month, clamped to the length of the target month, so
+    /// January 31 plus one month is the last day of February.
+    ///
+    /// This clamping replaces the earlier behaviour of returning `datetime`
+    /// unchanged when the target day did not exist.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the result is outside the range of `DateTime<Utc>`; use
+    /// [`TimeManipulator::checked_add_duration`] to handle that case.
     pub fn add_duration(datetime: DateTime<Utc>, amount: i64, unit: DurationUnit) -> DateTime<Utc> {
-        match unit {
-            DurationUnit::Years => {
-                let new_year = datetime.year() + amount as i32;
-                datetime.with_year(new_year).unwrap_or(datetime)
-            }
-            DurationUnit::Months => {
-                let total_months = datetime.month0() as i64 + amount;
-                let new_year = datetime.year() + (total_months / 12) as i32;
-                let new_month = (total_months % 12) as u32 + 1;
-                datetime
-                    .with_year(new_year)
-                    .and_then(|dt| dt.with_month(new_month))
-                    .unwrap_or(datetime)
-            }
-            DurationUnit::Weeks => datetime + Duration::weeks(amount),
-            DurationUnit::Days => datetime + Duration::days(amount),
-            DurationUnit::Hours => datetime + Duration::hours(amount),
-            DurationUnit::Minutes => datetime + Duration::minutes(amount),
-            DurationUnit::Seconds => datetime + Duration::seconds(amount),
-            DurationUnit::Milliseconds => datetime + Duration::milliseconds(amount),
-            DurationUnit::Microseconds => datetime + Duration::microseconds(amount),
-            DurationUnit::Nanoseconds => datetime + Duration::nanoseconds(amount),
-        }
+        Self::checked_add_duration(datetime, amount, unit).expect("date out of range")
+    }
+
+    /// Adds a duration like [`TimeManipulator::add_duration`], returning `None`
+    /// when the result is out of range.
+    pub fn checked_add_duration(
+        datetime: DateTime<Utc>,
+        amount: i64,
+        unit: DurationUnit,
+    ) -> Option<DateTime<Utc>> {
+        let duration = match unit {
+            DurationUnit::Years => return Self::add_months(datetime, amount.checked_mul(12)?),
+            DurationUnit::Months => return Self::add_months(datetime, amount),
+            DurationUnit::Weeks => Duration::try_weeks(amount)?,
+            DurationUnit::Days => Duration::try_days(amount)?,
+            DurationUnit::Hours => Duration::try_hours(amount)?,
+            DurationUnit::Minutes => Duration::try_minutes(amount)?,
+            DurationUnit::Seconds => Duration::try_seconds(amount)?,
+            DurationUnit::Milliseconds => Duration::try_milliseconds(amount)?,
+            DurationUnit::Microseconds => Duration::microseconds(amount),
+            DurationUnit::Nanoseconds => Duration::nanoseconds(amount),
+        };
+        datetime.checked_add_signed(duration)
+    }
+
+    /// Moves by whole months, clamping the day to the end of the target month.
+    fn add_months(datetime: DateTime<Utc>, months: i64) -> Option<DateTime<Utc>> {
+        let total = (datetime.year() as i64 * 12 + datetime.month0() as i64).checked_add(months)?;
+        let year = i32::try_from(total.div_euclid(12)).ok()?;
+        let month = total.rem_euclid(12) as u32 + 1;
+        let day = datetime.day().min(days_in_month(year, month));
+        let date = NaiveDate::from_ymd_opt(year, month, day)?;
+        Some(date.and_time(datetime.time()).and_utc())
     }
 
     /// Subtracts a duration from the given DateTime<Utc>.
@@ -110,12 +131,13 @@ impl TimeManipulator {
                 datetime1.year() == datetime2.year() && datetime1.month() == datetime2.month()
             }
             DurationUnit::Weeks => datetime1.iso_week() == datetime2.iso_week(),
-            DurationUnit::Days => datetime1.date() == datetime2.date(),
+            DurationUnit::Days => datetime1.date_naive() == datetime2.date_naive(),
             DurationUnit::Hours => {
-                datetime1.date() == datetime2.date() && datetime1.hour() == datetime2.hour()
+                datetime1.date_naive() == datetime2.date_naive()
+                    && datetime1.hour() == datetime2.hour()
             }
             DurationUnit::Minutes => {
-                datetime1.date() == datetime2.date()
+                datetime1.date_naive() == datetime2.date_naive()
                     && datetime1.hour() == datetime2.hour()
                     && datetime1.minute() == datetime2.minute()
             }
@@ -126,7 +148,9 @@ impl TimeManipulator {
             DurationUnit::Microseconds => {
                 datetime1.timestamp_micros() == datetime2.timestamp_micros()
             }
-            DurationUnit::Nanoseconds => datetime1.timestamp_nanos() == datetime2.timestamp_nanos(),
+            DurationUnit::Nanoseconds => {
+                datetime1.timestamp_nanos_opt() == datetime2.timestamp_nanos_opt()
+            }
         }
     }
 }
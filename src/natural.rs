@@ -0,0 +1,593 @@
+// src/natural.rs
+use crate::error::DateError;
+use crate::locale::LocaleManager;
+use crate::manipulator::{DurationUnit, TimeManipulator};
+use crate::parser::out_of_range;
+use crate::utils::days_in_month;
+use crate::DateX;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Utc, Weekday};
+
+/// The words and phrases the natural-language parser recognises for one
+/// language. Phrases may span several words (e.g. "from now"); weekday and
+/// month names come from [`LocaleManager`].
+#[derive(Debug, Clone)]
+pub struct NaturalLexicon {
+    pub now: &'static [&'static str],
+    pub today: &'static [&'static str],
+    pub tomorrow: &'static [&'static str],
+    pub yesterday: &'static [&'static str],
+    pub next: &'static [&'static str],
+    pub last: &'static [&'static str],
+    pub this: &'static [&'static str],
+    /// Introduces a future offset: "in 2 weeks".
+    pub in_future: &'static [&'static str],
+    /// Follows a past offset: "3 days ago".
+    pub ago: &'static [&'static str],
+    /// Follows a future offset: "3 days from now".
+    pub from_now: &'static [&'static str],
+    pub at: &'static [&'static str],
+    pub first_day_of: &'static [&'static str],
+    pub last_day_of: &'static [&'static str],
+    /// Filler words skipped before a unit or weekday, such as "the".
+    pub articles: &'static [&'static str],
+    pub noon: &'static [&'static str],
+    pub midnight: &'static [&'static str],
+    pub am: &'static [&'static str],
+    pub pm: &'static [&'static str],
+    pub units: &'static [(&'static str, DurationUnit)],
+    pub numbers: &'static [(&'static str, i64)],
+}
+
+/// The English lexicon.
+pub const ENGLISH_LEXICON: NaturalLexicon = NaturalLexicon {
+    now: &["now", "right now"],
+    today: &["today"],
+    tomorrow: &["tomorrow"],
+    yesterday: &["yesterday"],
+    next: &["next"],
+    last: &["last", "previous"],
+    this: &["this", "coming"],
+    in_future: &["in"],
+    ago: &["ago"],
+    from_now: &["from now", "later"],
+    at: &["at", "@"],
+    first_day_of: &["first day of", "start of", "beginning of"],
+    last_day_of: &["last day of", "end of"],
+    articles: &["the"],
+    noon: &["noon", "midday"],
+    midnight: &["midnight"],
+    am: &["am", "a.m."],
+    pm: &["pm", "p.m."],
+    units: &[
+        ("second", DurationUnit::Seconds),
+        ("seconds", DurationUnit::Seconds),
+        ("sec", DurationUnit::Seconds),
+        ("secs", DurationUnit::Seconds),
+        ("minute", DurationUnit::Minutes),
+        ("minutes", DurationUnit::Minutes),
+        ("min", DurationUnit::Minutes),
+        ("mins", DurationUnit::Minutes),
+        ("hour", DurationUnit::Hours),
+        ("hours", DurationUnit::Hours),
+        ("day", DurationUnit::Days),
+        ("days", DurationUnit::Days),
+        ("week", DurationUnit::Weeks),
+        ("weeks", DurationUnit::Weeks),
+        ("fortnight", DurationUnit::Weeks),
+        ("month", DurationUnit::Months),
+        ("months", DurationUnit::Months),
+        ("year", DurationUnit::Years),
+        ("years", DurationUnit::Years),
+    ],
+    numbers: &[
+        ("a", 1),
+        ("an", 1),
+        ("one", 1),
+        ("two", 2),
+        ("three", 3),
+        ("four", 4),
+        ("five", 5),
+        ("six", 6),
+        ("seven", 7),
+        ("eight", 8),
+        ("nine", 9),
+        ("ten", 10),
+        ("eleven", 11),
+        ("twelve", 12),
+    ],
+};
+
+/// What a date clause resolved to: a whole day, or an exact instant that keeps
+/// the reference time of day.
+enum DateClause {
+    Day(NaiveDate),
+    Instant(DateTime<Utc>),
+}
+
+/// Resolves relative, human-written date expressions against a reference instant.
+pub struct NaturalParser<'a> {
+    input: &'a str,
+    tokens: Vec<(usize, String)>,
+    pos: usize,
+    reference: DateTime<Utc>,
+    lexicon: &'a NaturalLexicon,
+    locale: &'a LocaleManager,
+    locale_code: &'a str,
+    ambiguous_hour: bool,
+}
+
+impl<'a> NaturalParser<'a> {
+    /// Parses an expression such as "tomorrow at 3pm", "next Friday",
+    /// "in 2 weeks" or "last day of the month".
+    ///
+    /// Day expressions resolve to midnight unless a time is given; offsets such
+    /// as "in 2 hours" keep the reference time of day.
+    pub fn parse(
+        input: &str,
+        reference: &DateX,
+        locale: &LocaleManager,
+        locale_code: &str,
+    ) -> Result<DateX, DateError> {
+        let tokens = input
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|token| !token.is_empty())
+            .map(|token| {
+                let offset = token.as_ptr() as usize - input.as_ptr() as usize;
+                (offset, token.to_lowercase())
+            })
+            .collect();
+        let mut parser = NaturalParser {
+            input,
+            tokens,
+            pos: 0,
+            reference: reference.datetime,
+            lexicon: locale.natural_lexicon(locale_code),
+            locale,
+            locale_code,
+            ambiguous_hour: false,
+        };
+        parser.parse_expression()
+    }
+
+    fn parse_expression(&mut self) -> Result<DateX, DateError> {
+        let mut date: Option<DateClause> = None;
+        let mut time: Option<NaiveTime> = None;
+
+        while self.pos < self.tokens.len() {
+            if time.is_none() {
+                if let Some(parsed) = self.parse_time()? {
+                    time = Some(parsed);
+                    continue;
+                }
+            }
+            if date.is_none() {
+                if let Some(parsed) = self.parse_date()? {
+                    date = Some(parsed);
+                    continue;
+                }
+            }
+            return Err(self.unrecognized());
+        }
+
+        let datetime = match (date, time) {
+            (None, None) => return Err(self.unrecognized()),
+            (Some(DateClause::Instant(instant)), None) => instant,
+            (Some(DateClause::Day(day)), None) => day.and_time(NaiveTime::MIN).and_utc(),
+            (date, Some(time)) => {
+                let day = match date {
+                    Some(DateClause::Day(day)) => day,
+                    Some(DateClause::Instant(instant)) => instant.date_naive(),
+                    None => self.reference.date_naive(),
+                };
+                day.and_time(time).and_utc()
+            }
+        };
+        if self.ambiguous_hour {
+            let candidates = vec![
+                DateX { datetime },
+                DateX {
+                    datetime: datetime + Duration::hours(12),
+                },
+            ];
+            return Err(self.ambiguous(candidates));
+        }
+        Ok(DateX { datetime })
+    }
+
+    /// Parses a time of day: "at 3pm", "15:30", "3:30 pm", "noon", "midnight".
+    fn parse_time(&mut self) -> Result<Option<NaiveTime>, DateError> {
+        let start = self.pos;
+        let explicit = self.eat_any(self.lexicon.at);
+
+        if self.eat_any(self.lexicon.noon) {
+            return Ok(NaiveTime::from_hms_opt(12, 0, 0));
+        }
+        if self.eat_any(self.lexicon.midnight) {
+            return Ok(Some(NaiveTime::MIN));
+        }
+
+        let token = match self.tokens.get(self.pos) {
+            Some((_, token)) => token.clone(),
+            None => {
+                self.pos = start;
+                return Ok(None);
+            }
+        };
+        let digits_end = token
+            .find(|c: char| !c.is_ascii_digit() && c != ':')
+            .unwrap_or(token.len());
+        let (clock, suffix) = token.split_at(digits_end);
+        let (hour, minute) = match clock.split_once(':') {
+            Some((h, m)) if m.len() == 2 => (h.parse::<u32>().ok(), m.parse::<u32>().ok()),
+            Some(_) => (None, None),
+            None => (clock.parse::<u32>().ok(), None),
+        };
+        let hour = match hour {
+            Some(hour) if clock.len() <= 5 => hour,
+            _ => {
+                self.pos = start;
+                return Ok(None);
+            }
+        };
+        self.pos += 1;
+
+        let meridiem = if suffix.is_empty() {
+            if self.eat_any(self.lexicon.am) {
+                Some(false)
+            } else if self.eat_any(self.lexicon.pm) {
+                Some(true)
+            } else {
+                None
+            }
+        } else if self.lexicon.am.contains(&suffix) {
+            Some(false)
+        } else if self.lexicon.pm.contains(&suffix) {
+            Some(true)
+        } else {
+            self.pos = start;
+            return Ok(None);
+        };
+
+        // A bare number is only a time when introduced by "at" or given a meridiem.
+        if minute.is_none() && meridiem.is_none() && !explicit {
+            self.pos = start;
+            return Ok(None);
+        }
+
+        let minute = minute.unwrap_or(0);
+        let hour = match meridiem {
+            Some(is_pm) => {
+                if !(1..=12).contains(&hour) {
+                    return Err(out_of_range("hour", hour));
+                }
+                match (is_pm, hour) {
+                    (false, 12) => 0,
+                    (true, 12) => 12,
+                    (true, h) => h + 12,
+                    (false, h) => h,
+                }
+            }
+            None if minute == 0 && (1..=12).contains(&hour) && !clock.contains(':') => {
+                // "at 3" could be 03:00 or 15:00; reported once the day is known.
+                self.ambiguous_hour = true;
+                hour
+            }
+            None => hour,
+        };
+
+        NaiveTime::from_hms_opt(hour, minute, 0)
+            .map(Some)
+            .ok_or_else(|| {
+                out_of_range(if hour > 23 { "hour" } else { "minute" }, hour.max(minute))
+            })
+    }
+
+    /// Parses a date clause, returning `None` without consuming anything when
+    /// the next tokens do not start one.
+    fn parse_date(&mut self) -> Result<Option<DateClause>, DateError> {
+        let lexicon = self.lexicon;
+        let today = self.reference.date_naive();
+
+        if self.eat_any(lexicon.now) {
+            return Ok(Some(DateClause::Instant(self.reference)));
+        }
+        if self.eat_any(lexicon.today) {
+            return Ok(Some(DateClause::Day(today)));
+        }
+        if self.eat_any(lexicon.tomorrow) {
+            return Ok(Some(DateClause::Day(today + Duration::days(1))));
+        }
+        if self.eat_any(lexicon.yesterday) {
+            return Ok(Some(DateClause::Day(today - Duration::days(1))));
+        }
+
+        let start = self.pos;
+        let first = self.eat_any(lexicon.first_day_of);
+        if first || self.eat_any(lexicon.last_day_of) {
+            self.eat_any(lexicon.articles);
+            let shift = self.parse_direction().unwrap_or(0);
+            let unit = match self.parse_unit() {
+                Some(unit @ (DurationUnit::Months | DurationUnit::Years)) => unit,
+                _ => {
+                    self.pos = start;
+                    return Ok(None);
+                }
+            };
+            let shifted = self.shift(shift, unit)?.date_naive();
+            let (year, month) = (shifted.year(), shifted.month());
+            let day = match (unit, first) {
+                (DurationUnit::Months, true) => NaiveDate::from_ymd_opt(year, month, 1),
+                (DurationUnit::Months, false) => {
+                    NaiveDate::from_ymd_opt(year, month, days_in_month(year, month))
+                }
+                (_, true) => NaiveDate::from_ymd_opt(year, 1, 1),
+                (_, false) => NaiveDate::from_ymd_opt(year, 12, 31),
+            };
+            return Ok(day.map(DateClause::Day));
+        }
+
+        if let Some(direction) = self.parse_direction() {
+            if let Some(weekday) = self.parse_weekday() {
+                let day = match direction {
+                    1 => next_weekday(today, weekday),
+                    -1 => previous_weekday(today, weekday),
+                    _ => {
+                        let monday =
+                            today - Duration::days(today.weekday().num_days_from_monday() as i64);
+                        monday + Duration::days(weekday.num_days_from_monday() as i64)
+                    }
+                };
+                return Ok(Some(DateClause::Day(day)));
+            }
+            if let Some(unit) = self.parse_unit() {
+                let shifted = self.shift(direction, unit)?;
+                return Ok(Some(DateClause::Instant(shifted)));
+            }
+            self.pos = start;
+            return Ok(None);
+        }
+
+        if let Some(weekday) = self.parse_weekday() {
+            if today.weekday() == weekday {
+                let candidates = [today, today + Duration::days(7)]
+                    .iter()
+                    .map(|day| DateX {
+                        datetime: day.and_time(NaiveTime::MIN).and_utc(),
+                    })
+                    .collect();
+                return Err(self.ambiguous(candidates));
+            }
+            return Ok(Some(DateClause::Day(next_weekday(today, weekday))));
+        }
+
+        let future = self.eat_any(lexicon.in_future);
+        if let Some(amount) = self.parse_number() {
+            if let Some(unit) = self.parse_unit() {
+                let sign: i64 = if future || self.eat_any(lexicon.from_now) {
+                    1
+                } else if self.eat_any(lexicon.ago) {
+                    -1
+                } else {
+                    self.pos = start;
+                    return Ok(None);
+                };
+                let amount = amount
+                    .checked_mul(sign)
+                    .ok_or_else(|| unit_out_of_range(unit, amount))?;
+                let shifted = self.shift(amount, unit)?;
+                return Ok(Some(DateClause::Instant(shifted)));
+            }
+        }
+
+        self.pos = start;
+        Ok(None)
+    }
+
+    /// Moves the reference by `amount` units, failing when the result is
+    /// outside the supported range.
+    fn shift(&self, amount: i64, unit: DurationUnit) -> Result<DateTime<Utc>, DateError> {
+        TimeManipulator::checked_add_duration(self.reference, amount, unit)
+            .ok_or_else(|| unit_out_of_range(unit, amount))
+    }
+
+    /// Parses "next", "last" or "this" as +1, -1 or 0.
+    fn parse_direction(&mut self) -> Option<i64> {
+        if self.eat_any(self.lexicon.next) {
+            Some(1)
+        } else if self.eat_any(self.lexicon.last) {
+            Some(-1)
+        } else if self.eat_any(self.lexicon.this) {
+            Some(0)
+        } else {
+            None
+        }
+    }
+
+    fn parse_unit(&mut self) -> Option<DurationUnit> {
+        let token = &self.tokens.get(self.pos)?.1;
+        let unit = self
+            .lexicon
+            .units
+            .iter()
+            .find(|(name, _)| name == token)
+            .map(|(_, unit)| *unit)?;
+        self.pos += 1;
+        Some(unit)
+    }
+
+    fn parse_number(&mut self) -> Option<i64> {
+        let token = &self.tokens.get(self.pos)?.1;
+        let number = match token.parse::<i64>() {
+            Ok(number) => number,
+            Err(_) => self
+                .lexicon
+                .numbers
+                .iter()
+                .find(|(word, _)| word == token)
+                .map(|(_, number)| *number)?,
+        };
+        self.pos += 1;
+        Some(number)
+    }
+
+    /// Matches a full weekday name or an abbreviation of at least three letters.
+    fn parse_weekday(&mut self) -> Option<Weekday> {
+        let token = &self.tokens.get(self.pos)?.1;
+        if token.chars().count() < 3 {
+            return None;
+        }
+        let weekday = WEEKDAYS.iter().copied().find(|weekday| {
+            let name = self
+                .locale
+                .weekday_name(self.locale_code, *weekday)
+                .to_lowercase();
+            name.starts_with(token.as_str())
+        })?;
+        self.pos += 1;
+        Some(weekday)
+    }
+
+    /// Consumes the first phrase in `phrases` that the upcoming tokens spell out.
+    fn eat_any(&mut self, phrases: &[&str]) -> bool {
+        for phrase in phrases {
+            let words: Vec<&str> = phrase.split_whitespace().collect();
+            let matches = words.iter().enumerate().all(|(i, word)| {
+                self.tokens
+                    .get(self.pos + i)
+                    .is_some_and(|(_, t)| t == word)
+            });
+            if matches && !words.is_empty() {
+                self.pos += words.len();
+                return true;
+            }
+        }
+        false
+    }
+
+    fn unrecognized(&self) -> DateError {
+        let position = self
+            .tokens
+            .get(self.pos)
+            .map_or(self.input.len(), |(offset, _)| *offset);
+        DateError::UnrecognizedExpression {
+            input: self.input.to_string(),
+            position,
+        }
+    }
+
+    fn ambiguous(&self, candidates: Vec<DateX>) -> DateError {
+        DateError::AmbiguousExpression {
+            input: self.input.to_string(),
+            candidates,
+        }
+    }
+}
+
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+/// The first `weekday` strictly after `date`.
+fn next_weekday(date: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let days = (weekday.num_days_from_monday() as i64
+        - date.weekday().num_days_from_monday() as i64)
+        .rem_euclid(7);
+    date + Duration::days(if days == 0 { 7 } else { days })
+}
+
+/// The last `weekday` strictly before `date`.
+fn previous_weekday(date: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let days = (date.weekday().num_days_from_monday() as i64
+        - weekday.num_days_from_monday() as i64)
+        .rem_euclid(7);
+    date - Duration::days(if days == 0 { 7 } else { days })
+}
+
+/// The error for an offset of `amount` units that leaves the supported range.
+fn unit_out_of_range(unit: DurationUnit, amount: i64) -> DateError {
+    let field = match unit {
+        DurationUnit::Years => "years",
+        DurationUnit::Months => "months",
+        DurationUnit::Weeks => "weeks",
+        DurationUnit::Days => "days",
+        DurationUnit::Hours => "hours",
+        DurationUnit::Minutes => "minutes",
+        DurationUnit::Seconds => "seconds",
+        DurationUnit::Milliseconds => "milliseconds",
+        DurationUnit::Microseconds => "microseconds",
+        DurationUnit::Nanoseconds => "nanoseconds",
+    };
+    DateError::FieldOutOfRange {
+        field,
+        value: amount,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference() -> DateX {
+        DateX::parse("2024-01-31 10:00:00", "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    fn natural(input: &str) -> Result<String, DateError> {
+        DateX::parse_natural(input, &reference()).map(|date| date.format("%Y-%m-%d %H:%M"))
+    }
+
+    #[test]
+    fn month_offsets_clamp_to_the_end_of_the_month() {
+        assert_eq!(natural("in 1 month").unwrap(), "2024-02-29 10:00");
+        assert_eq!(natural("1 month ago").unwrap(), "2023-12-31 10:00");
+        assert_eq!(natural("in 13 months").unwrap(), "2025-02-28 10:00");
+        assert_eq!(
+            natural("first day of next month").unwrap(),
+            "2024-02-01 00:00"
+        );
+        assert_eq!(
+            natural("last day of next month").unwrap(),
+            "2024-02-29 00:00"
+        );
+    }
+
+    #[test]
+    fn previous_month_wraps_into_the_previous_year() {
+        assert_eq!(
+            natural("last day of last month").unwrap(),
+            "2023-12-31 00:00"
+        );
+        assert_eq!(
+            natural("first day of last month").unwrap(),
+            "2023-12-01 00:00"
+        );
+        assert_eq!(
+            natural("first day of last year").unwrap(),
+            "2023-01-01 00:00"
+        );
+    }
+
+    #[test]
+    fn huge_offsets_are_errors() {
+        assert_eq!(
+            natural("in 99999999999999 days"),
+            Err(DateError::FieldOutOfRange {
+                field: "days",
+                value: 99999999999999
+            })
+        );
+        assert_eq!(
+            natural("in 2147483647 years"),
+            Err(DateError::FieldOutOfRange {
+                field: "years",
+                value: 2147483647
+            })
+        );
+        assert!(natural("9223372036854775807 months ago").is_err());
+    }
+}
@@ -0,0 +1,559 @@
+// src/parsed.rs
+use crate::error::{DateError, ParseDiagnostic};
+use crate::parser::{out_of_range, DateParser};
+use crate::timezone::{LocalTimePolicy, TimeZoneHandler};
+use crate::DateX;
+use chrono::{
+    DateTime, Datelike, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use std::fmt;
+use std::str::FromStr;
+
+/// The fields found in an input, before any defaults are applied.
+///
+/// Returned by `DateParser::parse_partial` for inputs such as "March 2024" or
+/// "14:30". Each conversion succeeds only when the fields it needs are present.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Parsed {
+    pub(crate) year: Option<i32>,
+    pub(crate) month: Option<u32>,
+    pub(crate) day: Option<u32>,
+    pub(crate) ordinal: Option<u32>,
+    pub(crate) weekday: Option<Weekday>,
+    pub(crate) iso_year: Option<i32>,
+    pub(crate) iso_week: Option<u32>,
+    pub(crate) hour: Option<u32>,
+    pub(crate) hour_12: bool,
+    pub(crate) is_pm: Option<bool>,
+    pub(crate) minute: Option<u32>,
+    pub(crate) second: Option<u32>,
+    pub(crate) nanosecond: Option<u32>,
+    pub(crate) offset: Option<i32>,
+    zone: Option<Zone>,
+    pub(crate) timestamp: Option<i64>,
+}
+
+/// A time zone read by %Z: either a fixed offset or an IANA zone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Zone {
+    Fixed(i32),
+    Named(Tz),
+}
+
+/// A calendar month of a specific year, such as March 2024.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct YearMonth {
+    pub year: i32,
+    pub month: u32,
+}
+
+impl fmt::Display for YearMonth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}", self.year, self.month)
+    }
+}
+
+impl Parsed {
+    /// Returns the year, if present.
+    pub fn year(&self) -> Option<i32> {
+        self.year
+    }
+
+    /// Returns the month (1-12), if present.
+    pub fn month(&self) -> Option<u32> {
+        self.month
+    }
+
+    /// Returns the day of the month, if present.
+    pub fn day(&self) -> Option<u32> {
+        self.day
+    }
+
+    /// Returns the day of the year (%j), if present.
+    pub fn ordinal(&self) -> Option<u32> {
+        self.ordinal
+    }
+
+    /// Returns the weekday, if present.
+    pub fn weekday(&self) -> Option<Weekday> {
+        self.weekday
+    }
+
+    /// Returns the ISO week-based year (%G), if present.
+    pub fn iso_year(&self) -> Option<i32> {
+        self.iso_year
+    }
+
+    /// Returns the ISO week number (%V), if present.
+    pub fn iso_week(&self) -> Option<u32> {
+        self.iso_week
+    }
+
+    /// Returns the hour on the 24-hour clock, if present. A 12-hour value needs its
+    /// AM/PM marker to be converted.
+    pub fn hour(&self) -> Option<u32> {
+        self.hour_24().ok().flatten()
+    }
+
+    /// Returns the minute, if present.
+    pub fn minute(&self) -> Option<u32> {
+        self.minute
+    }
+
+    /// Returns the second, if present.
+    pub fn second(&self) -> Option<u32> {
+        self.second
+    }
+
+    /// Returns the fraction of a second in nanoseconds, if present.
+    pub fn nanosecond(&self) -> Option<u32> {
+        self.nanosecond
+    }
+
+    /// Returns the UTC offset in seconds from %z or a fixed %Z, if present.
+    pub fn offset(&self) -> Option<i32> {
+        match (self.offset, self.zone) {
+            (Some(offset), _) | (None, Some(Zone::Fixed(offset))) => Some(offset),
+            _ => None,
+        }
+    }
+
+    /// Returns the IANA time zone from %Z, if present.
+    pub fn timezone(&self) -> Option<Tz> {
+        match self.zone {
+            Some(Zone::Named(tz)) => Some(tz),
+            _ => None,
+        }
+    }
+
+    /// Returns the Unix timestamp (%s), if present.
+    pub fn timestamp(&self) -> Option<i64> {
+        self.timestamp
+    }
+
+    /// Builds the instant. Needs a timestamp, or a complete date and at least an hour
+    /// and minute; an offset or zone is applied when present, otherwise UTC.
+    pub fn to_datex(&self) -> Result<DateX, DateError> {
+        if let Some(timestamp) = self.timestamp {
+            return timestamp_to_datetime(timestamp).map(|datetime| DateX { datetime });
+        }
+        let naive = NaiveDateTime::new(self.to_naive_date()?, self.to_naive_time()?);
+        self.localize(naive).map(|datetime| DateX { datetime })
+    }
+
+    /// Builds the calendar date from year/month/day, year/day-of-year or ISO
+    /// year/week/weekday.
+    pub fn to_naive_date(&self) -> Result<NaiveDate, DateError> {
+        self.check_date()?;
+        self.resolve_date(NaiveDate::default())
+    }
+
+    /// Builds the time of day. Needs an hour and minute; seconds and fractions are
+    /// zero when absent.
+    pub fn to_naive_time(&self) -> Result<NaiveTime, DateError> {
+        let hour = self.hour_24()?.ok_or(DateError::MissingField("hour"))?;
+        let minute = self.minute.ok_or(DateError::MissingField("minute"))?;
+        if self.nanosecond.is_some() && self.second.is_none() {
+            return Err(DateError::MissingField("second"));
+        }
+        DateParser::build_time(
+            hour,
+            minute,
+            self.second.unwrap_or(0),
+            self.nanosecond.unwrap_or(0),
+        )
+    }
+
+    /// Builds the year and month, either given directly or taken from a complete date.
+    pub fn to_year_month(&self) -> Result<YearMonth, DateError> {
+        match (self.year, self.month) {
+            (Some(year), Some(month)) => {
+                if !(1..=12).contains(&month) {
+                    return Err(out_of_range("month", month));
+                }
+                Ok(YearMonth { year, month })
+            }
+            _ => self.to_naive_date().map(|date| YearMonth {
+                year: date.year(),
+                month: date.month(),
+            }),
+        }
+    }
+
+    /// Stores the value captured for a format token (named as in the regex).
+    pub(crate) fn apply(&mut self, name: &str, text: &str) -> Result<(), DateError> {
+        let number = || text.parse::<u32>().map_err(|_| not_a_number(name, text));
+        match name {
+            "Y" => self.year = Some(number()? as i32),
+            "y" => {
+                let y = number()? as i32;
+                self.year = Some(if y < 70 { 2000 + y } else { 1900 + y });
+            }
+            "m" => self.month = Some(number()?),
+            "d" | "e" | "D" => self.day = Some(number()?),
+            "j" => self.ordinal = Some(number()?),
+            "u" => self.weekday = Weekday::try_from(number()? as u8 - 1).ok(),
+            "w" => self.weekday = Weekday::try_from((number()? as u8 + 6) % 7).ok(),
+            "V" => self.iso_week = Some(number()?),
+            "G" => self.iso_year = Some(number()? as i32),
+            "H" => self.hour = Some(number()?),
+            "I" => {
+                self.hour = Some(number()?);
+                self.hour_12 = true;
+            }
+            "M" => self.minute = Some(number()?),
+            "S" => self.second = Some(number()?),
+            "f" => {
+                let scale = 10u32.pow(9 - text.len().min(9) as u32);
+                self.nanosecond = Some(number()? * scale);
+            }
+            "ms" => self.nanosecond = Some(number()? * 1_000_000),
+            "z" => self.offset = Some(parse_offset(text)?),
+            "Z" => self.zone = Some(parse_zone(text)?),
+            "s" => {
+                let timestamp = text.parse::<i64>().map_err(|_| not_a_number(name, text))?;
+                self.timestamp = Some(timestamp);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Rejects fields that the result would ignore; in strict mode also requires a
+    /// complete date (unless a default date fills it) and a well-formed time.
+    pub(crate) fn check_strict(&self, has_default: bool) -> Result<(), DateError> {
+        let by_week = self.iso_week.is_some();
+        let by_ordinal = self.ordinal.is_some();
+
+        if self.timestamp.is_some() {
+            let others = self.year.is_some() || self.month.is_some() || self.day.is_some();
+            if others || by_week || by_ordinal || self.hour.is_some() {
+                return Err(DateError::UnusedField("timestamp"));
+            }
+            return Ok(());
+        }
+        if by_ordinal && (self.month.is_some() || self.day.is_some()) {
+            return Err(DateError::UnusedField("day of year"));
+        }
+        if by_week && (self.month.is_some() || self.day.is_some() || by_ordinal) {
+            return Err(DateError::UnusedField("week"));
+        }
+        if self.iso_year.is_some() && !by_week {
+            return Err(DateError::UnusedField("ISO year"));
+        }
+        if self.is_pm.is_some() && !self.hour_12 {
+            return Err(DateError::UnusedField("am/pm"));
+        }
+
+        if !has_default {
+            self.check_date()?;
+        }
+        self.hour_24()?;
+        if self.minute.is_some() && self.hour.is_none() {
+            return Err(DateError::MissingField("hour"));
+        }
+        if self.second.is_some() && self.minute.is_none() {
+            return Err(DateError::MissingField("minute"));
+        }
+        if self.nanosecond.is_some() && self.second.is_none() {
+            return Err(DateError::MissingField("second"));
+        }
+        Ok(())
+    }
+
+    /// Requires the fields of one complete way to name a date.
+    fn check_date(&self) -> Result<(), DateError> {
+        let by_week = self.iso_week.is_some() && self.month.is_none() && self.ordinal.is_none();
+        let by_ordinal = self.ordinal.is_some() && self.month.is_none();
+        if self.year.is_none() && self.iso_year.is_none() {
+            return Err(DateError::MissingField("year"));
+        }
+        if by_week {
+            if self.weekday.is_none() {
+                return Err(DateError::MissingField("weekday"));
+            }
+        } else if !by_ordinal {
+            if self.month.is_none() {
+                return Err(DateError::MissingField("month"));
+            }
+            if self.day.is_none() {
+                return Err(DateError::MissingField("day"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Converts a 12-hour value with its AM/PM marker to the 24-hour clock.
+    fn hour_24(&self) -> Result<Option<u32>, DateError> {
+        let hour = match self.hour {
+            Some(hour) => hour,
+            None => return Ok(None),
+        };
+        if !self.hour_12 {
+            return Ok(Some(hour));
+        }
+        let is_pm = self.is_pm.ok_or(DateError::MissingField("am/pm"))?;
+        if !(1..=12).contains(&hour) {
+            return Err(out_of_range("hour", hour));
+        }
+        Ok(Some(match (is_pm, hour) {
+            (false, 12) => 0,
+            (true, 12) => 12,
+            (true, h) => h + 12,
+            (false, h) => h,
+        }))
+    }
+
+    /// Builds the UTC instant, taking missing date parts from `default_date` (or
+    /// 1970-01-01) and missing time parts as zero. A Unix timestamp wins over every
+    /// other field.
+    pub(crate) fn resolve(
+        &self,
+        default_date: Option<NaiveDate>,
+    ) -> Result<DateTime<Utc>, DateError> {
+        if let Some(timestamp) = self.timestamp {
+            return timestamp_to_datetime(timestamp);
+        }
+
+        let date = self.resolve_date(default_date.unwrap_or_default())?;
+        // A 12-hour value without a marker is read as AM.
+        let hour = match (self.hour, self.hour_12, self.is_pm) {
+            (Some(_), true, None) => Parsed {
+                is_pm: Some(false),
+                ..self.clone()
+            }
+            .hour_24()?,
+            _ => self.hour_24()?,
+        };
+        let time = DateParser::build_time(
+            hour.unwrap_or(0),
+            self.minute.unwrap_or(0),
+            self.second.unwrap_or(0),
+            self.nanosecond.unwrap_or(0),
+        )?;
+        self.localize(NaiveDateTime::new(date, time))
+    }
+
+    /// Resolves the date, then checks a parsed weekday against it. A date made
+    /// only of defaults is not checked, so "Monday 14:30" parses.
+    fn resolve_date(&self, default_date: NaiveDate) -> Result<NaiveDate, DateError> {
+        let date = self.resolve_date_fields(default_date)?;
+        let has_date = self.year.is_some()
+            || self.month.is_some()
+            || self.day.is_some()
+            || self.ordinal.is_some()
+            || self.iso_week.is_some();
+        if let (Some(weekday), true) = (self.weekday, has_date) {
+            if date.weekday() != weekday {
+                return Err(DateError::WeekdayMismatch { date, weekday });
+            }
+        }
+        Ok(date)
+    }
+
+    fn resolve_date_fields(&self, default_date: NaiveDate) -> Result<NaiveDate, DateError> {
+        if let (Some(week), None, None) = (self.iso_week, self.month, self.ordinal) {
+            let year = self.iso_year.or(self.year).unwrap_or(default_date.year());
+            let weekday = self.weekday.unwrap_or(Weekday::Mon);
+            return NaiveDate::from_isoywd_opt(year, week, weekday)
+                .ok_or(out_of_range("week", week));
+        }
+        let year = self.year.or(self.iso_year).unwrap_or(default_date.year());
+        if let (Some(ordinal), None) = (self.ordinal, self.month) {
+            return NaiveDate::from_yo_opt(year, ordinal)
+                .ok_or(out_of_range("day of year", ordinal));
+        }
+        DateParser::build_date(
+            year,
+            self.month.unwrap_or(default_date.month()),
+            self.day.unwrap_or(default_date.day()),
+        )
+    }
+
+    /// Interprets a local date-time in the parsed offset or zone, or as UTC.
+    fn localize(&self, naive: NaiveDateTime) -> Result<DateTime<Utc>, DateError> {
+        let offset = match (self.zone, self.offset) {
+            (_, Some(offset)) | (Some(Zone::Fixed(offset)), None) => offset,
+            (Some(Zone::Named(tz)), None) => {
+                let local = TimeZoneHandler::resolve_local(tz, naive, LocalTimePolicy::Earliest)?;
+                return Ok(local.with_timezone(&Utc));
+            }
+            (None, None) => 0,
+        };
+        FixedOffset::east_opt(offset)
+            .and_then(|tz| tz.from_local_datetime(&naive).single())
+            .map(|datetime| datetime.with_timezone(&Utc))
+            .ok_or(DateError::FieldOutOfRange {
+                field: "offset",
+                value: offset as i64,
+            })
+    }
+}
+
+fn timestamp_to_datetime(timestamp: i64) -> Result<DateTime<Utc>, DateError> {
+    DateTime::from_timestamp(timestamp, 0).ok_or(DateError::FieldOutOfRange {
+        field: "timestamp",
+        value: timestamp,
+    })
+}
+
+/// The error for a captured field that is not a plain ASCII number. The parser
+/// replaces it with a diagnostic pointing into the whole input.
+fn not_a_number(name: &str, text: &str) -> DateError {
+    DateError::PatternMismatch(Box::new(ParseDiagnostic {
+        input: text.to_string(),
+        format: format!("%{}", name),
+        position: 0,
+        token: Some(format!("%{}", name)),
+        expected: "a number".to_string(),
+        found: format!("{:?}", text),
+    }))
+}
+
+/// Converts "Z", "+0530" or "-05:00" to seconds east of UTC.
+pub(crate) fn parse_offset(text: &str) -> Result<i32, DateError> {
+    if text == "Z" {
+        return Ok(0);
+    }
+    let sign = if text.starts_with('-') { -1 } else { 1 };
+    let digits: String = text[1..].chars().filter(char::is_ascii_digit).collect();
+    let hours: i32 = digits[..2].parse().unwrap_or(0);
+    let minutes: i32 = digits.get(2..).and_then(|m| m.parse().ok()).unwrap_or(0);
+    if hours > 23 || minutes > 59 {
+        return Err(DateError::FieldOutOfRange {
+            field: "offset",
+            value: text[1..].parse().unwrap_or(0),
+        });
+    }
+    Ok(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Common time zone abbreviations and their offsets in hours. Abbreviations
+/// are not unique worldwide; these are the North American, European and
+/// Asia-Pacific readings, so CST is US Central (not China) and BST is British
+/// Summer Time (not Bangladesh).
+const ZONE_ABBREVIATIONS: [(&str, i32); 29] = [
+    ("UTC", 0),
+    ("UT", 0),
+    ("GMT", 0),
+    ("Z", 0),
+    ("WET", 0),
+    ("WEST", 1),
+    ("BST", 1),
+    ("CET", 1),
+    ("CEST", 2),
+    ("EET", 2),
+    ("EEST", 3),
+    ("MSK", 3),
+    ("JST", 9),
+    ("KST", 9),
+    ("AEST", 10),
+    ("AEDT", 11),
+    ("NZST", 12),
+    ("NZDT", 13),
+    ("HST", -10),
+    ("AKST", -9),
+    ("AKDT", -8),
+    ("PST", -8),
+    ("PDT", -7),
+    ("MST", -7),
+    ("MDT", -6),
+    ("CST", -6),
+    ("CDT", -5),
+    ("EST", -5),
+    ("EDT", -4),
+];
+
+/// Converts an abbreviation such as "CEST" to seconds east of UTC.
+pub(crate) fn abbreviation_offset(text: &str) -> Option<i32> {
+    let upper = text.to_ascii_uppercase();
+    ZONE_ABBREVIATIONS
+        .iter()
+        .find(|(name, _)| *name == upper)
+        .map(|(_, hours)| hours * 3600)
+}
+
+/// Resolves a %Z value: an abbreviation such as "CEST", a numeric zone such as
+/// "+03", or an IANA name such as "Europe/Berlin".
+fn parse_zone(text: &str) -> Result<Zone, DateError> {
+    if text.starts_with(['+', '-']) {
+        return parse_offset(text).map(Zone::Fixed);
+    }
+    if let Some(offset) = abbreviation_offset(text) {
+        return Ok(Zone::Fixed(offset));
+    }
+    Tz::from_str(text)
+        .map(Zone::Named)
+        .map_err(|_| DateError::InvalidTimezone(text.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Parsed;
+    use crate::{DateError, DateX};
+    use chrono::{NaiveDate, Weekday};
+
+    fn mismatch_at(input: &str, format: &str) -> (usize, Option<String>) {
+        match DateX::parse(input, format) {
+            Err(DateError::PatternMismatch(d)) => (d.position, d.token),
+            other => panic!("expected a pattern mismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn non_ascii_digits_are_rejected() {
+        assert_eq!(
+            mismatch_at("２０２４-03-04", "%Y-%m-%d"),
+            (0, Some("%Y".to_string()))
+        );
+        assert_eq!(mismatch_at("2024-03-0４", "%Y-%m-%d"), (9, None));
+        assert_eq!(
+            mismatch_at("2024-03-04 09:05:07.1２３４", "%Y-%m-%d %H:%M:%S.%f"),
+            (21, None)
+        );
+    }
+
+    #[test]
+    fn captures_that_are_not_numbers_are_errors() {
+        let mut parsed = Parsed::default();
+        assert!(matches!(
+            parsed.apply("d", "0４"),
+            Err(DateError::PatternMismatch(_))
+        ));
+        assert_eq!(parsed.day, None);
+        parsed.apply("f", "123456789").unwrap();
+        assert_eq!(parsed.nanosecond, Some(123_456_789));
+        parsed.apply("f", "5").unwrap();
+        assert_eq!(parsed.nanosecond, Some(500_000_000));
+    }
+
+    #[test]
+    fn parsed_weekday_must_match_the_date() {
+        assert_eq!(
+            DateX::parse("Monday 2024-03-15", "%A %Y-%m-%d"),
+            Err(DateError::WeekdayMismatch {
+                date: NaiveDate::from_ymd_opt(2024, 3, 15).unwrap(),
+                weekday: Weekday::Mon
+            })
+        );
+        assert!(DateX::parse("Friday 2024-03-15", "%A %Y-%m-%d").is_ok());
+    }
+
+    #[test]
+    fn weekday_without_a_date_is_not_checked() {
+        let parsed = DateX::parse("Monday 14:30", "%A %H:%M").unwrap();
+        assert_eq!(parsed.format("%H:%M"), "14:30");
+        assert!(DateX::parse("Tue 09:00", "%a %H:%M").is_ok());
+        assert!(matches!(
+            DateX::parse("Monday 15 14:30", "%A %d %H:%M"),
+            Err(DateError::WeekdayMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn mismatch_message_names_both_weekdays() {
+        let error = DateX::parse("Mon 2024-03-15", "%a %Y-%m-%d").unwrap_err();
+        assert_eq!(error.to_string(), "2024-03-15 is a Friday, not a Monday");
+    }
+}
@@ -1,134 +1,754 @@
 // src/parser.rs
-use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use crate::error::{DateError, ParseDiagnostic};
+use crate::locale::LocaleManager;
+use crate::parsed::Parsed;
+use crate::DateX;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
 use regex::Regex;
-use std::collections::HashMap;
+use std::borrow::Cow;
+
+/// How closely the input must follow the format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    /// Missing fields take defaults and numeric fields may omit leading zeros.
+    #[default]
+    Standard,
+    /// Every date field must be present (or come from the default date), fields that
+    /// would be ignored are rejected and numeric fields must have their exact width.
+    Strict,
+    /// Surrounding whitespace and text are ignored, letters match regardless of case
+    /// and a space in the format matches any run of whitespace.
+    Lenient,
+}
+
+/// Options for [`DateParser::compile_with_options`] and `DateX::parse_with_options`.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    pub mode: ParseMode,
+    /// Supplies the year, month and day when the format does not contain them.
+    pub default_date: Option<NaiveDate>,
+}
+
+impl ParseOptions {
+    /// Options for strict parsing.
+    pub fn strict() -> Self {
+        ParseOptions {
+            mode: ParseMode::Strict,
+            ..Default::default()
+        }
+    }
+
+    /// Options for lenient parsing.
+    pub fn lenient() -> Self {
+        ParseOptions {
+            mode: ParseMode::Lenient,
+            ..Default::default()
+        }
+    }
+}
 
 /// A struct responsible for parsing date strings into `DateX` instances.
 pub struct DateParser;
 
 impl DateParser {
     /// Parses a date string according to the provided format string.
-    pub fn parse(date_str: &str, format: &str) -> Result<DateTime<Utc>, String> {
-        let regex_str = Self::format_to_regex(format);
-        let re = Regex::new(&regex_str).map_err(|e| e.to_string())?;
-        let caps = re
-            .captures(date_str)
-            .ok_or("Date string does not match format")?;
-
-        let mut date_parts: HashMap<&str, u32> = HashMap::new();
-        let mut year: i32 = 1970;
-        let mut hour_12 = false;
-        let mut is_pm = false;
-
-        for name in re.capture_names().flatten() {
-            if let Some(value) = caps.name(name) {
-                match name {
-                    "Y" => {
-                        year = value.as_str().parse::<i32>().map_err(|_| "Invalid year")?;
-                    }
-                    "y" => {
-                        let y = value.as_str().parse::<i32>().map_err(|_| "Invalid year")?;
-                        year = if y < 70 { 2000 + y } else { 1900 + y };
-                    }
-                    "m" => {
-                        date_parts.insert(
-                            "month",
-                            value.as_str().parse::<u32>().map_err(|_| "Invalid month")?,
-                        );
-                    }
-                    "d" => {
-                        date_parts.insert(
-                            "day",
-                            value.as_str().parse::<u32>().map_err(|_| "Invalid day")?,
-                        );
-                    }
-                    "H" | "I" => {
-                        date_parts.insert(
-                            "hour",
-                            value.as_str().parse::<u32>().map_err(|_| "Invalid hour")?,
-                        );
-                        if name == "I" {
-                            hour_12 = true;
-                        }
-                    }
-                    "M" => {
-                        date_parts.insert(
-                            "minute",
-                            value
-                                .as_str()
-                                .parse::<u32>()
-                                .map_err(|_| "Invalid minute")?,
-                        );
-                    }
-                    "S" => {
-                        date_parts.insert(
-                            "second",
-                            value
-                                .as_str()
-                                .parse::<u32>()
-                                .map_err(|_| "Invalid second")?,
-                        );
-                    }
-                    "p" => {
-                        is_pm = value.as_str().to_lowercase() == "pm";
-                    }
-                    // ... Additional tokens can be added here
-                    _ => {}
-                }
+    pub fn parse(date_str: &str, format: &str) -> Result<DateTime<Utc>, DateError> {
+        Self::compile(format)?
+            .parse(date_str)
+            .map(|datex| datex.datetime)
+    }
+
+    /// Parses a date string, matching %b %B %a %A against the month and weekday
+    /// names of the given locale regardless of case and accents.
+    pub fn parse_with_locale(
+        date_str: &str,
+        format: &str,
+        locale: &LocaleManager,
+        locale_code: &str,
+    ) -> Result<DateTime<Utc>, DateError> {
+        CompiledFormat::new(
+            format,
+            Cow::Borrowed(locale),
+            locale_code,
+            ParseOptions::default(),
+        )?
+        .parse(date_str)
+        .map(|datex| datex.datetime)
+    }
+
+    /// Parses the fields present in a date string without filling in missing ones,
+    /// e.g. "March 2024" with "%B %Y".
+    pub fn parse_partial(date_str: &str, format: &str) -> Result<Parsed, DateError> {
+        Self::compile(format)?.parse_partial(date_str)
+    }
+
+    /// Validates and compiles a format once so it can parse many inputs.
+    pub fn compile(format: &str) -> Result<CompiledFormat<'static>, DateError> {
+        Self::compile_with_locale(format, "en")
+    }
+
+    /// Compiles a format whose month and weekday names are in the given locale.
+    pub fn compile_with_locale(
+        format: &str,
+        locale_code: &str,
+    ) -> Result<CompiledFormat<'static>, DateError> {
+        CompiledFormat::new(
+            format,
+            Cow::Owned(LocaleManager::new()),
+            locale_code,
+            ParseOptions::default(),
+        )
+    }
+
+    /// Compiles a format with the given strictness and default date.
+    pub fn compile_with_options(
+        format: &str,
+        options: &ParseOptions,
+    ) -> Result<CompiledFormat<'static>, DateError> {
+        CompiledFormat::new(
+            format,
+            Cow::Owned(LocaleManager::new()),
+            "en",
+            options.clone(),
+        )
+    }
+
+    /// Builds a date, reporting which component is out of range.
+    pub(crate) fn build_date(year: i32, month: u32, day: u32) -> Result<NaiveDate, DateError> {
+        if !(1..=12).contains(&month) {
+            return Err(out_of_range("month", month));
+        }
+        NaiveDate::from_ymd_opt(year, month, day).ok_or(out_of_range("day", day))
+    }
+
+    /// Builds a time of day, reporting which component is out of range.
+    pub(crate) fn build_time(
+        hour: u32,
+        minute: u32,
+        second: u32,
+        nanos: u32,
+    ) -> Result<NaiveTime, DateError> {
+        if hour > 23 {
+            return Err(out_of_range("hour", hour));
+        }
+        if minute > 59 {
+            return Err(out_of_range("minute", minute));
+        }
+        NaiveTime::from_hms_nano_opt(hour, minute, second, nanos)
+            .ok_or(out_of_range("second", second))
+    }
+
+    /// Walks the format greedily to find where the input stops matching, and
+    /// describes the token expected there and the text found instead. Each step
+    /// uses the same regex as [`DateParser::format_to_regex`], so the walker
+    /// accepts exactly what the full pattern accepts.
+    fn diagnose(
+        date_str: &str,
+        format: &str,
+        mode: ParseMode,
+        locale: &LocaleManager,
+        locale_code: &str,
+    ) -> DateError {
+        let flags = if mode == ParseMode::Lenient {
+            "(?i)"
+        } else {
+            ""
+        };
+        let mut pos = 0;
+        let mut first = None;
+        let mut chars = format.chars();
+
+        while let Some(c) = chars.next() {
+            let (token, expected, pattern) = if c == '%' {
+                let t = chars.next().unwrap_or('%');
+                // Escapes for literal characters are reported as the literal itself.
+                let (token, expected) = match t {
+                    't' => (None, format!("{:?}", '\t')),
+                    'n' => (None, format!("{:?}", '\n')),
+                    '%' => (None, format!("{:?}", '%')),
+                    _ => (
+                        Some(format!("%{}", t)),
+                        Self::describe_token(t, mode, locale, locale_code),
+                    ),
+                };
+                let pattern = Self::token_regex(t, mode, locale, locale_code).unwrap_or_default();
+                (token, expected, pattern)
+            } else {
+                (None, format!("{:?}", c), Self::literal_regex(c, mode))
+            };
+            let next = Regex::new(&format!("{}^(?:{})", flags, pattern))
+                .ok()
+                .and_then(|regex| regex.find(&date_str[pos..]))
+                .map(|found| pos + found.end());
+            if first.is_none() && token.is_some() {
+                first = Some((pos, token.clone(), expected.clone()));
+            }
+            match next {
+                Some(next) => pos = next,
+                None => return mismatch(date_str, format, pos, token, expected),
             }
         }
 
-        let month = *date_parts.get("month").unwrap_or(&1);
-        let day = *date_parts.get("day").unwrap_or(&1);
-        let mut hour = *date_parts.get("hour").unwrap_or(&0);
-        let minute = *date_parts.get("minute").unwrap_or(&0);
-        let second = *date_parts.get("second").unwrap_or(&0);
+        if pos < date_str.len() {
+            return mismatch(
+                date_str,
+                format,
+                pos,
+                None,
+                "the end of the input".to_string(),
+            );
+        }
+        // Every token matched in turn, so the regex would have matched too; point at
+        // the first field rather than reporting a mismatch at the end of the input.
+        let (position, token, expected) =
+            first.unwrap_or_else(|| (0, None, "the end of the input".to_string()));
+        mismatch(date_str, format, position, token, expected)
+    }
 
-        if hour_12 {
-            if is_pm && hour != 12 {
-                hour += 12;
-            } else if !is_pm && hour == 12 {
-                hour = 0;
+    /// Describes what a format token matches, for diagnostics.
+    fn describe_token(
+        token: char,
+        mode: ParseMode,
+        locale: &LocaleManager,
+        locale_code: &str,
+    ) -> String {
+        let width = |what: &str| {
+            if mode == ParseMode::Strict {
+                format!("a 2-digit {}", what)
+            } else {
+                format!("a 1-2 digit {}", what)
             }
+        };
+        match token {
+            'Y' => "a 4-digit year".to_string(),
+            'y' => "a 2-digit year".to_string(),
+            'm' => width("month"),
+            'd' => width("day"),
+            'H' => width("hour (0-23)"),
+            'I' => width("hour (1-12)"),
+            'M' => width("minute"),
+            'S' => width("second"),
+            'V' => width("ISO week"),
+            'e' => "a day of the month".to_string(),
+            'G' => "a 4-digit ISO year".to_string(),
+            'j' => "a day of the year".to_string(),
+            'u' => "a weekday number (1-7)".to_string(),
+            'w' => "a weekday number (0-6)".to_string(),
+            'f' => "fractional seconds".to_string(),
+            '3' => "3-digit milliseconds".to_string(),
+            's' => "a Unix timestamp".to_string(),
+            'b' | 'B' => "a month name".to_string(),
+            'a' | 'A' => "a weekday name".to_string(),
+            'D' => format!(
+                "a day with an ordinal suffix such as 3{}",
+                locale.ordinal_suffix(locale_code, 3)
+            ),
+            'z' => "a UTC offset such as +0100".to_string(),
+            'Z' => "a time zone".to_string(),
+            'p' => format!(
+                "{} or {}",
+                locale.day_period(locale_code, false),
+                locale.day_period(locale_code, true)
+            ),
+            other => format!("{:?}", other),
         }
+    }
 
-        let naive_date = NaiveDate::from_ymd_opt(year, month, day).ok_or("Invalid date")?;
-        let naive_time = NaiveTime::from_hms_opt(hour, minute, second).ok_or("Invalid time")?;
-        let naive_datetime = NaiveDateTime::new(naive_date, naive_time);
+    /// The regex for the token `%<token>`, or `None` if the token is unknown.
+    /// Numbers are ASCII digits only, so every capture parses as an integer.
+    /// Ordinal suffixes and AM/PM markers are those the formatter writes for
+    /// the locale, so formatted dates parse back.
+    fn token_regex(
+        token: char,
+        mode: ParseMode,
+        locale: &LocaleManager,
+        locale_code: &str,
+    ) -> Option<String> {
+        let strict = mode == ParseMode::Strict;
+        let two = if strict { r"[0-9]{2}" } else { r"[0-9]{1,2}" };
+        let regex = match token {
+            'Y' => r"(?P<Y>[0-9]{4})".to_string(),
+            'y' => r"(?P<y>[0-9]{2})".to_string(),
+            'm' => format!("(?P<m>{})", two),
+            'd' => format!("(?P<d>{})", two),
+            'H' => format!("(?P<H>{})", two),
+            'I' => format!("(?P<I>{})", two),
+            'M' => format!("(?P<M>{})", two),
+            'S' => format!("(?P<S>{})", two),
+            'p' => format!("(?P<p>{})", day_period_regex(locale, locale_code)),
+            'b' => r"(?P<b>\p{L}+)".to_string(),
+            'B' => r"(?P<B>\p{L}+)".to_string(),
+            'a' => r"(?P<a>\p{L}+)".to_string(),
+            'A' => r"(?P<A>\p{L}+)".to_string(),
+            'e' => r"\s?(?P<e>[0-9]{1,2})".to_string(),
+            'D' => format!(
+                "(?P<D>[0-9]{{1,2}})(?:{})",
+                ordinal_suffix_regex(locale, locale_code)
+            ),
+            'j' if strict => r"(?P<j>[0-9]{3})".to_string(),
+            'j' => r"(?P<j>[0-9]{1,3})".to_string(),
+            'u' => r"(?P<u>[1-7])".to_string(),
+            'w' => r"(?P<w>[0-6])".to_string(),
+            'V' => format!("(?P<V>{})", two),
+            'G' => r"(?P<G>[0-9]{4})".to_string(),
+            'f' => r"(?P<f>[0-9]{1,9})".to_string(),
+            '3' => r"(?P<ms>[0-9]{3})".to_string(),
+            'z' => r"(?P<z>Z|[+-][0-9]{2}:?[0-9]{2})".to_string(),
+            'Z' => r"(?P<Z>[A-Za-z][A-Za-z0-9_+\-/]*|[+-][0-9]{2}(?::?[0-9]{2})?)".to_string(),
+            's' => r"(?P<s>-?[0-9]{1,18})".to_string(),
+            't' => "\t".to_string(),
+            'n' => "\n".to_string(),
+            '%' => "%".to_string(),
+            _ => return None,
+        };
+        Some(regex)
+    }
 
-        Ok(DateTime::<Utc>::from_utc(naive_datetime, Utc))
+    /// The regex for a literal format character; in lenient mode a space matches
+    /// any run of whitespace.
+    fn literal_regex(c: char, mode: ParseMode) -> String {
+        if c == ' ' && mode == ParseMode::Lenient {
+            r"\s+".to_string()
+        } else if "()[]{}.*+?^$|\\".contains(c) {
+            format!("\\{}", c)
+        } else {
+            c.to_string()
+        }
     }
 
-    fn format_to_regex(format: &str) -> String {
+    /// Translates a format into a regex, rejecting unknown tokens, a trailing '%'
+    /// and fields that appear twice. Only lenient mode leaves the regex unanchored.
+    fn format_to_regex(
+        format: &str,
+        mode: ParseMode,
+        locale: &LocaleManager,
+        locale_code: &str,
+    ) -> Result<String, DateError> {
         let mut regex_str = String::new();
-        let mut chars = format.chars().peekable();
+        let mut chars = format.chars();
+        let mut seen = String::new();
 
         while let Some(c) = chars.next() {
             if c == '%' {
-                if let Some(next_char) = chars.next() {
-                    match next_char {
-                        'Y' => regex_str.push_str(r"(?P<Y>\d{4})"),
-                        'y' => regex_str.push_str(r"(?P<y>\d{2})"),
-                        'm' => regex_str.push_str(r"(?P<m>\d{1,2})"),
-                        'd' => regex_str.push_str(r"(?P<d>\d{1,2})"),
-                        'H' => regex_str.push_str(r"(?P<H>\d{1,2})"),
-                        'I' => regex_str.push_str(r"(?P<I>\d{1,2})"),
-                        'M' => regex_str.push_str(r"(?P<M>\d{1,2})"),
-                        'S' => regex_str.push_str(r"(?P<S>\d{1,2})"),
-                        'p' => regex_str.push_str(r"(?P<p>AM|PM|am|pm)"),
-                        '%' => regex_str.push('%'),
-                        // ... Additional tokens can be added here
-                        _ => regex_str.push(next_char),
+                let next_char = chars
+                    .next()
+                    .ok_or_else(|| DateError::InvalidFormat("format ends with '%'".to_string()))?;
+                if next_char.is_alphanumeric() && !matches!(next_char, 't' | 'n') {
+                    if seen.contains(next_char) {
+                        return Err(DateError::InvalidFormat(format!(
+                            "%{} appears more than once",
+                            next_char
+                        )));
                     }
+                    seen.push(next_char);
                 }
+                let token =
+                    Self::token_regex(next_char, mode, locale, locale_code).ok_or_else(|| {
+                        DateError::InvalidFormat(format!("unknown token %{}", next_char))
+                    })?;
+                regex_str.push_str(&token);
             } else {
-                if "()[]{}.*+?^$|\\".contains(c) {
-                    regex_str.push('\\');
+                regex_str.push_str(&Self::literal_regex(c, mode));
+            }
+        }
+
+        Ok(match mode {
+            ParseMode::Lenient => format!("(?i){}", regex_str),
+            _ => format!("^{}$", regex_str),
+        })
+    }
+}
+
+/// A format validated and compiled once, for parsing many inputs.
+///
+/// Formats made only of fixed-width numeric fields (%Y %y %m %d %H %M %S %j %3)
+/// and ASCII literals are matched byte by byte without a regex when the input
+/// has the padded width, e.g. "2024-03-05 09:07:00" for "%Y-%m-%d %H:%M:%S".
+///
+/// The locale is borrowed when the caller supplies one, so
+/// [`DateParser::parse_with_locale`] does not copy its name tables.
+pub struct CompiledFormat<'a> {
+    format: String,
+    regex: Regex,
+    fixed: Option<Vec<FixedItem>>,
+    locale: Cow<'a, LocaleManager>,
+    locale_code: String,
+    options: ParseOptions,
+}
+
+/// One element of a fixed-width format.
+#[derive(Debug, Clone, Copy)]
+enum FixedItem {
+    Literal(u8),
+    Field(&'static str, usize),
+}
+
+impl<'a> CompiledFormat<'a> {
+    fn new(
+        format: &str,
+        locale: Cow<'a, LocaleManager>,
+        locale_code: &str,
+        options: ParseOptions,
+    ) -> Result<Self, DateError> {
+        let regex_str = DateParser::format_to_regex(format, options.mode, &locale, locale_code)?;
+        let regex = Regex::new(&regex_str).map_err(|e| DateError::InvalidFormat(e.to_string()))?;
+        Ok(CompiledFormat {
+            format: format.to_string(),
+            regex,
+            fixed: match options.mode {
+                ParseMode::Lenient => None,
+                _ => Self::fixed_items(format),
+            },
+            locale,
+            locale_code: locale_code.to_string(),
+            options,
+        })
+    }
+
+    /// Returns the format string this was compiled from.
+    pub fn as_str(&self) -> &str {
+        &self.format
+    }
+
+    /// Parses an input with this format.
+    pub fn parse(&self, date_str: &str) -> Result<DateX, DateError> {
+        let parsed = self.parse_partial(date_str)?;
+        if self.options.mode == ParseMode::Strict {
+            parsed.check_strict(self.options.default_date.is_some())?;
+        }
+        parsed
+            .resolve(self.options.default_date)
+            .map(|datetime| DateX { datetime })
+    }
+
+    /// Parses an input into the fields it contains, without applying defaults.
+    pub fn parse_partial(&self, date_str: &str) -> Result<Parsed, DateError> {
+        let date_str = match self.options.mode {
+            ParseMode::Lenient => date_str.trim(),
+            _ => date_str,
+        };
+        let mut parsed = Parsed::default();
+        if !self.parse_fixed(date_str, &mut parsed)? {
+            self.parse_regex(date_str, &mut parsed)?;
+        }
+        Ok(parsed)
+    }
+
+    /// Splits a format into fixed-width items, or `None` if it has any variable-width token.
+    fn fixed_items(format: &str) -> Option<Vec<FixedItem>> {
+        let mut items = Vec::new();
+        let mut chars = format.chars();
+        while let Some(c) = chars.next() {
+            let item = match c {
+                '%' => match chars.next()? {
+                    'Y' => FixedItem::Field("Y", 4),
+                    'y' => FixedItem::Field("y", 2),
+                    'm' => FixedItem::Field("m", 2),
+                    'd' => FixedItem::Field("d", 2),
+                    'H' => FixedItem::Field("H", 2),
+                    'M' => FixedItem::Field("M", 2),
+                    'S' => FixedItem::Field("S", 2),
+                    'j' => FixedItem::Field("j", 3),
+                    '3' => FixedItem::Field("ms", 3),
+                    '%' => FixedItem::Literal(b'%'),
+                    _ => return None,
+                },
+                c if c.is_ascii() => FixedItem::Literal(c as u8),
+                _ => return None,
+            };
+            items.push(item);
+        }
+        Some(items)
+    }
+
+    /// Tries the fixed-width fast path. Returns `false` when the input does not have
+    /// the padded shape, so the regex path can decide (e.g. "2024-3-5").
+    fn parse_fixed(&self, date_str: &str, parsed: &mut Parsed) -> Result<bool, DateError> {
+        let items = match &self.fixed {
+            Some(items) => items,
+            None => return Ok(false),
+        };
+        let bytes = date_str.as_bytes();
+        let width: usize = items
+            .iter()
+            .map(|item| match item {
+                FixedItem::Literal(_) => 1,
+                FixedItem::Field(_, width) => *width,
+            })
+            .sum();
+        if bytes.len() != width {
+            return Ok(false);
+        }
+
+        let mut fields = Vec::with_capacity(items.len());
+        let mut pos = 0;
+        for item in items {
+            match *item {
+                FixedItem::Literal(b) if bytes[pos] == b => pos += 1,
+                FixedItem::Field(name, width)
+                    if bytes[pos..pos + width].iter().all(u8::is_ascii_digit) =>
+                {
+                    fields.push((name, &date_str[pos..pos + width]));
+                    pos += width;
+                }
+                _ => return Ok(false),
+            }
+        }
+        for (name, text) in fields {
+            parsed.apply(name, text)?;
+        }
+        Ok(true)
+    }
+
+    fn parse_regex(&self, date_str: &str, parsed: &mut Parsed) -> Result<(), DateError> {
+        let caps = self.regex.captures(date_str).ok_or_else(|| {
+            DateParser::diagnose(
+                date_str,
+                &self.format,
+                self.options.mode,
+                &self.locale,
+                &self.locale_code,
+            )
+        })?;
+        let unknown_name = |token: &str, what: &str, position: usize| {
+            let token = Some(format!("%{}", token));
+            let expected = format!("a {} name in locale '{}'", what, self.locale_code);
+            mismatch(date_str, &self.format, position, token, expected)
+        };
+
+        for name in self.regex.capture_names().flatten() {
+            if let Some(value) = caps.name(name) {
+                let text = value.as_str();
+                match name {
+                    "b" | "B" => {
+                        let month = self
+                            .locale
+                            .parse_month(&self.locale_code, text)
+                            .ok_or_else(|| unknown_name(name, "month", value.start()))?;
+                        parsed.month = Some(month);
+                    }
+                    "p" => {
+                        let pm = self.locale.day_period(&self.locale_code, true);
+                        parsed.is_pm = Some(text.to_lowercase() == pm.to_lowercase());
+                    }
+                    "a" | "A" => {
+                        let weekday = self
+                            .locale
+                            .parse_weekday(&self.locale_code, text)
+                            .ok_or_else(|| unknown_name(name, "weekday", value.start()))?;
+                        parsed.weekday = Some(weekday);
+                    }
+                    _ => parsed.apply(name, text).map_err(|e| match e {
+                        // Point a capture that is not a number at its place in the input.
+                        DateError::PatternMismatch(diagnostic) => {
+                            let token = if name == "ms" {
+                                "%3"
+                            } else {
+                                &diagnostic.format
+                            };
+                            mismatch(
+                                date_str,
+                                &self.format,
+                                value.start(),
+                                Some(token.to_string()),
+                                diagnostic.expected,
+                            )
+                        }
+                        e => e,
+                    })?,
                 }
-                regex_str.push(c);
             }
         }
+        Ok(())
+    }
+}
+
+/// Alternatives for the locale's AM/PM markers as written, in lower case and in
+/// upper case; "Pm" is only accepted in lenient mode.
+fn day_period_regex(locale: &LocaleManager, locale_code: &str) -> String {
+    let mut periods = Vec::new();
+    for period in [false, true].map(|is_pm| locale.day_period(locale_code, is_pm)) {
+        for variant in [
+            period.to_string(),
+            period.to_lowercase(),
+            period.to_uppercase(),
+        ] {
+            if !periods.contains(&variant) {
+                periods.push(variant);
+            }
+        }
+    }
+    alternatives(periods)
+}
+
+/// Alternatives for every ordinal suffix the locale uses for days 1-31.
+fn ordinal_suffix_regex(locale: &LocaleManager, locale_code: &str) -> String {
+    let suffixes = (1..=31)
+        .map(|day| locale.ordinal_suffix(locale_code, day).to_string())
+        .collect();
+    alternatives(suffixes)
+}
+
+/// Joins literal strings into a regex alternation, longest first so that a
+/// suffix never matches only a prefix of a longer one.
+fn alternatives(mut items: Vec<String>) -> String {
+    items.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+    items.dedup();
+    let escaped: Vec<String> = items.iter().map(|item| regex::escape(item)).collect();
+    escaped.join("|")
+}
+
+/// Builds a pattern mismatch, quoting the word of the input found at `position`.
+fn mismatch(
+    date_str: &str,
+    format: &str,
+    position: usize,
+    token: Option<String>,
+    expected: String,
+) -> DateError {
+    let rest = &date_str[position..];
+    let word_len: usize = rest
+        .chars()
+        .take_while(|c| c.is_alphanumeric())
+        .map(char::len_utf8)
+        .sum();
+    let found = match rest.chars().next() {
+        None => "the end of the input".to_string(),
+        Some(c) if word_len == 0 => format!("{:?}", c),
+        Some(_) => format!("{:?}", &rest[..word_len]),
+    };
+    DateError::PatternMismatch(Box::new(ParseDiagnostic {
+        input: date_str.to_string(),
+        format: format.to_string(),
+        position,
+        token,
+        expected,
+        found,
+    }))
+}
+
+pub(crate) fn out_of_range(field: &'static str, value: u32) -> DateError {
+    DateError::FieldOutOfRange {
+        field,
+        value: value as i64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(input: &str, format: &str, options: ParseOptions) -> ParseDiagnostic {
+        match DateX::parse_with_options(input, format, &options) {
+            Err(DateError::PatternMismatch(diagnostic)) => *diagnostic,
+            other => panic!("expected a pattern mismatch, got {:?}", other),
+        }
+    }
+
+    fn standard(input: &str, format: &str) -> ParseDiagnostic {
+        diagnostic(input, format, ParseOptions::default())
+    }
+
+    #[test]
+    fn parse_with_locale_borrows_the_locale() {
+        let locale = LocaleManager::new();
+        let parsed =
+            DateParser::parse_with_locale("5 de marzo de 2024", "%e de %B de %Y", &locale, "es");
+        assert_eq!(parsed.unwrap().format("%Y-%m-%d").to_string(), "2024-03-05");
+        let d = match DateParser::parse_with_locale(
+            "5 de março de 2024",
+            "%e de %B de %Y",
+            &locale,
+            "es",
+        ) {
+            Err(DateError::PatternMismatch(diagnostic)) => diagnostic,
+            other => panic!("expected a pattern mismatch, got {:?}", other),
+        };
+        assert_eq!(d.expected, "a month name in locale 'es'");
+    }
+
+    #[test]
+    fn localized_output_parses_back() {
+        let locale = LocaleManager::new();
+        let date = DateX::parse("2024-03-04 15:00", "%Y-%m-%d %H:%M").unwrap();
+        for (format, code) in [
+            ("%A %D %B %Y %I:%M %p", "es"),
+            ("%A %D %B %Y %I:%M %p", "en"),
+            ("%a %e %b %Y %I %p", "es"),
+        ] {
+            let text = date.format_with_locale(format, code);
+            let parsed = DateParser::parse_with_locale(&text, format, &locale, code);
+            assert_eq!(parsed, Ok(date.datetime), "{} in {}", text, code);
+        }
+        assert_eq!(
+            date.format_with_locale("%A %D %B %Y %p", "es"),
+            "Lunes 4º Marzo 2024 p. m."
+        );
+    }
+
+    #[test]
+    fn locale_markers_are_described() {
+        let locale = LocaleManager::new();
+        let d = match DateParser::parse_with_locale("3 PM", "%I %p", &locale, "es") {
+            Err(DateError::PatternMismatch(diagnostic)) => diagnostic,
+            other => panic!("expected a pattern mismatch, got {:?}", other),
+        };
+        assert_eq!((d.position, d.expected.as_str()), (2, "a. m. or p. m."));
+    }
+
+    #[test]
+    fn meridiem_is_case_sensitive_outside_lenient_mode() {
+        let d = standard("03:00 Am", "%I:%M %p");
+        assert_eq!((d.position, d.token.as_deref()), (6, Some("%p")));
+        assert_eq!(d.expected, "AM or PM");
+        assert_eq!(d.found, "\"Am\"");
+        assert!(
+            DateX::parse_with_options("03:00 Am", "%I:%M %p", &ParseOptions::lenient()).is_ok()
+        );
+    }
+
+    #[test]
+    fn ordinal_suffix_must_be_english() {
+        let d = standard("3xx March 2024", "%D %B %Y");
+        assert_eq!((d.position, d.token.as_deref()), (0, Some("%D")));
+        assert_eq!(d.found, "\"3xx\"");
+    }
+
+    #[test]
+    fn time_zone_follows_the_regex_grammar() {
+        let d = standard("10:00 #UTC", "%H:%M %Z");
+        assert_eq!((d.position, d.token.as_deref()), (6, Some("%Z")));
+        assert_eq!(d.found, "'#'");
+    }
+
+    #[test]
+    fn day_of_month_may_be_space_padded() {
+        let d = standard("March  5 20x4", "%B %e %Y");
+        assert_eq!((d.position, d.token.as_deref()), (9, Some("%Y")));
+        assert!(DateX::parse("March  5 2024", "%B %e %Y").is_ok());
+    }
+
+    #[test]
+    fn weekday_numbers_and_offsets_follow_the_regex_grammar() {
+        let d = standard("2024-W10-8", "%G-W%V-%u");
+        assert_eq!((d.position, d.token.as_deref()), (9, Some("%u")));
+        let d = standard("10:00 0100", "%H:%M %z");
+        assert_eq!((d.position, d.token.as_deref()), (6, Some("%z")));
+    }
+
+    #[test]
+    fn trailing_text_is_reported_after_the_last_field() {
+        let d = standard("2024-03-05x", "%Y-%m-%d");
+        assert_eq!((d.position, d.token), (10, None));
+        assert_eq!(d.expected, "the end of the input");
+        assert_eq!(d.found, "\"x\"");
+    }
 
-        format!("^{}$", regex_str)
+    #[test]
+    fn missing_fields_are_reported_at_the_end_of_the_input() {
+        let d = standard("2024-03", "%Y-%m-%d");
+        assert_eq!((d.position, d.token), (7, None));
+        assert_eq!(d.expected, "'-'");
+        assert_eq!(d.found, "the end of the input");
     }
 }
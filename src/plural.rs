@@ -0,0 +1,100 @@
+// src/plural.rs
+
+/// The CLDR plural categories. Which numbers fall in each category depends on
+/// the language, e.g. Polish uses `Few` for 2-4 and `Many` for 5-21.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PluralCategory {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+/// A word or pattern for each plural category of a language; `Other` is used
+/// for any category that is missing.
+pub type PluralForms = &'static [(PluralCategory, &'static str)];
+
+/// Picks the form for `category`, falling back to `Other`.
+pub(crate) fn select(forms: PluralForms, category: PluralCategory) -> &'static str {
+    forms
+        .iter()
+        .find(|(c, _)| *c == category)
+        .or_else(|| forms.iter().find(|(c, _)| *c == PluralCategory::Other))
+        .map_or("{}", |(_, form)| *form)
+}
+
+/// The CLDR rule for English (and other languages with only "one" and "other").
+pub fn english(n: u64) -> PluralCategory {
+    if n == 1 {
+        PluralCategory::One
+    } else {
+        PluralCategory::Other
+    }
+}
+
+/// The CLDR rule for Spanish: exact millions take `Many` ("1 millón de días").
+pub fn spanish(n: u64) -> PluralCategory {
+    match n {
+        1 => PluralCategory::One,
+        _ if n != 0 && n.is_multiple_of(1_000_000) => PluralCategory::Many,
+        _ => PluralCategory::Other,
+    }
+}
+
+/// The CLDR rule for Polish integers: 1 is `One`, 2-4, 22-24, 32-34, ... are
+/// `Few` and every other integer is `Many`. `Other` is only used for fractions.
+pub fn polish(n: u64) -> PluralCategory {
+    let (last, last_two) = (n % 10, n % 100);
+    if n == 1 {
+        PluralCategory::One
+    } else if (2..=4).contains(&last) && !(12..=14).contains(&last_two) {
+        PluralCategory::Few
+    } else {
+        PluralCategory::Many
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PluralCategory::{Few, Many, One, Other};
+    use super::*;
+
+    #[test]
+    fn english_rule() {
+        assert_eq!(english(0), Other);
+        assert_eq!(english(1), One);
+        assert_eq!(english(2), Other);
+        assert_eq!(english(21), Other);
+    }
+
+    #[test]
+    fn spanish_rule() {
+        assert_eq!(spanish(0), Other);
+        assert_eq!(spanish(1), One);
+        assert_eq!(spanish(2), Other);
+        assert_eq!(spanish(1_000_000), Many);
+        assert_eq!(spanish(2_000_000), Many);
+        assert_eq!(spanish(1_000_001), Other);
+    }
+
+    #[test]
+    fn polish_rule() {
+        assert_eq!(polish(1), One);
+        assert_eq!(polish(2), Few);
+        assert_eq!(polish(5), Many);
+        assert_eq!(polish(12), Many);
+        assert_eq!(polish(22), Few);
+        assert_eq!(polish(112), Many);
+        assert_eq!(polish(0), Many);
+    }
+
+    #[test]
+    fn select_falls_back_to_other() {
+        let forms: PluralForms = &[(One, "{} day"), (Other, "{} days")];
+        assert_eq!(select(forms, One), "{} day");
+        assert_eq!(select(forms, Few), "{} days");
+        assert_eq!(select(&[], One), "{}");
+    }
+}
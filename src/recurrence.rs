@@ -1,17 +1,54 @@
 // src/recurrence.rs
-use chrono::{DateTime, Duration, Utc};
+use crate::error::DateError;
+use crate::locale::LocaleManager;
+use crate::timezone::{LocalTimePolicy, TimeZoneHandler};
+use crate::{DateX, ZonedDateX};
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc, Weekday,
+};
+use chrono_tz::Tz;
 use std::collections::VecDeque;
+use std::fmt;
+use std::iter::{FusedIterator, Peekable};
+use std::str::FromStr;
 
-/// Represents a recurrence rule for generating dates.
-#[derive(Debug, Clone)]
+/// Represents an RFC 5545 recurrence rule for generating dates.
+///
+/// Empty `by_*` lists mean the part is absent. Parts that RFC 5545 derives from
+/// the start date (e.g. the day of the month for a monthly rule) are filled in
+/// when the rule is expanded.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RecurrenceRule {
     pub frequency: Frequency,
     pub interval: u32,
     pub count: Option<u32>,
     pub until: Option<DateTime<Utc>>,
+    /// Whether UNTIL is a DATE (`20241231`) rather than a DATE-TIME; it is
+    /// written back in the same form.
+    pub until_is_date: bool,
+    /// BYSECOND: seconds of the minute (0-59).
+    pub by_second: Vec<u32>,
+    /// BYMINUTE: minutes of the hour (0-59).
+    pub by_minute: Vec<u32>,
+    /// BYHOUR: hours of the day (0-23).
+    pub by_hour: Vec<u32>,
+    /// BYDAY: weekdays, optionally with an ordinal such as `2MO` or `-1FR`.
+    pub by_day: Vec<WeekdayNum>,
+    /// BYMONTHDAY: days of the month (1-31 or -31 to -1).
+    pub by_month_day: Vec<i32>,
+    /// BYYEARDAY: days of the year (1-366 or -366 to -1).
+    pub by_year_day: Vec<i32>,
+    /// BYWEEKNO: weeks of the year (1-53 or -53 to -1), numbered from `week_start`.
+    pub by_week_no: Vec<i32>,
+    /// BYMONTH: months of the year (1-12).
+    pub by_month: Vec<u32>,
+    /// BYSETPOS: positions within the occurrences of each period (1-366 or -366 to -1).
+    pub by_set_pos: Vec<i32>,
+    /// WKST: the day on which the week starts.
+    pub week_start: Weekday,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Frequency {
     Secondly,
     Minutely,
@@ -22,66 +59,1807 @@ pub enum Frequency {
     Yearly,
 }
 
+/// A BYDAY entry: a weekday, optionally restricted to its nth occurrence within
+/// the month or year (negative ordinals count from the end).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WeekdayNum {
+    pub ordinal: Option<i32>,
+    pub weekday: Weekday,
+}
+
+impl WeekdayNum {
+    /// Every occurrence of the weekday, e.g. `MO`.
+    pub fn every(weekday: Weekday) -> Self {
+        WeekdayNum {
+            ordinal: None,
+            weekday,
+        }
+    }
+
+    /// The nth occurrence of the weekday, e.g. `2MO` or `-1FR`.
+    pub fn nth(ordinal: i32, weekday: Weekday) -> Self {
+        WeekdayNum {
+            ordinal: Some(ordinal),
+            weekday,
+        }
+    }
+}
+
+impl fmt::Display for Frequency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Frequency::Secondly => "SECONDLY",
+            Frequency::Minutely => "MINUTELY",
+            Frequency::Hourly => "HOURLY",
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Frequency {
+    type Err = DateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "SECONDLY" => Ok(Frequency::Secondly),
+            "MINUTELY" => Ok(Frequency::Minutely),
+            "HOURLY" => Ok(Frequency::Hourly),
+            "DAILY" => Ok(Frequency::Daily),
+            "WEEKLY" => Ok(Frequency::Weekly),
+            "MONTHLY" => Ok(Frequency::Monthly),
+            "YEARLY" => Ok(Frequency::Yearly),
+            _ => Err(invalid_part("FREQ", s)),
+        }
+    }
+}
+
+impl fmt::Display for WeekdayNum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(ordinal) = self.ordinal {
+            write!(f, "{}", ordinal)?;
+        }
+        write!(f, "{}", weekday_code(self.weekday))
+    }
+}
+
+impl FromStr for WeekdayNum {
+    type Err = DateError;
+
+    /// Parses a BYDAY entry such as `MO`, `+2TU` or `-1FR`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s.len().saturating_sub(2);
+        let weekday = s
+            .get(split..)
+            .and_then(parse_weekday_code)
+            .ok_or_else(|| invalid_part("BYDAY", s))?;
+        match &s[..split] {
+            "" => Ok(WeekdayNum::every(weekday)),
+            ordinal => ordinal
+                .parse::<i32>()
+                .map(|ordinal| WeekdayNum::nth(ordinal, weekday))
+                .map_err(|_| invalid_part("BYDAY", s)),
+        }
+    }
+}
+
+/// Phrases used by [`RecurrenceRule::to_text`] for one language. Month and
+/// weekday names come from [`LocaleManager`].
+struct RuleWords {
+    /// Singular and plural unit names, indexed by frequency rank.
+    units: [(&'static str, &'static str); 7],
+    every: &'static str,
+    and: &'static str,
+    on_weekdays: &'static str,
+    nth_weekday: &'static str,
+    on_month_days: &'static str,
+    on_year_days: &'static str,
+    in_weeks: &'static str,
+    in_months: &'static str,
+    at_times: &'static str,
+    at_hours: &'static str,
+    at_minutes: &'static str,
+    at_seconds: &'static str,
+    only: &'static str,
+    once: &'static str,
+    times: &'static str,
+    until: &'static str,
+    lowercase_names: bool,
+    ordinal: fn(i32) -> String,
+    month_day: fn(i32) -> String,
+    date: fn(u32, &str, i32) -> String,
+}
+
+const ENGLISH_RULE_WORDS: RuleWords = RuleWords {
+    units: [
+        ("second", "seconds"),
+        ("minute", "minutes"),
+        ("hour", "hours"),
+        ("day", "days"),
+        ("week", "weeks"),
+        ("month", "months"),
+        ("year", "years"),
+    ],
+    every: "every",
+    and: "and",
+    on_weekdays: "on",
+    nth_weekday: "the {ordinal} {weekday}",
+    on_month_days: "on the",
+    on_year_days: "on day {days} of the year",
+    in_weeks: "in week",
+    in_months: "in",
+    at_times: "at",
+    at_hours: "at hour",
+    at_minutes: "at minute",
+    at_seconds: "at second",
+    only: "only the",
+    once: "once",
+    times: "times",
+    until: "until",
+    lowercase_names: false,
+    ordinal: |n| match n {
+        1 => "first".to_string(),
+        2 => "second".to_string(),
+        3 => "third".to_string(),
+        4 => "fourth".to_string(),
+        5 => "fifth".to_string(),
+        -1 => "last".to_string(),
+        n if n < 0 => format!(
+            "{}{} to last",
+            -n,
+            crate::utils::get_ordinal_suffix(-n as u32)
+        ),
+        n => format!("{}{}", n, crate::utils::get_ordinal_suffix(n as u32)),
+    },
+    month_day: |n| match n {
+        -1 => "last day".to_string(),
+        n if n < 0 => format!(
+            "{}{} to last day",
+            -n,
+            crate::utils::get_ordinal_suffix(-n as u32)
+        ),
+        n => format!("{}{}", n, crate::utils::get_ordinal_suffix(n as u32)),
+    },
+    date: |day, month, year| format!("{} {}, {}", month, day, year),
+};
+
+const SPANISH_RULE_WORDS: RuleWords = RuleWords {
+    units: [
+        ("segundo", "segundos"),
+        ("minuto", "minutos"),
+        ("hora", "horas"),
+        ("día", "días"),
+        ("semana", "semanas"),
+        ("mes", "meses"),
+        ("año", "años"),
+    ],
+    every: "cada",
+    and: "y",
+    on_weekdays: "el",
+    nth_weekday: "{ordinal} {weekday}",
+    on_month_days: "el día",
+    on_year_days: "el día {days} del año",
+    in_weeks: "en la semana",
+    in_months: "en",
+    at_times: "a las",
+    at_hours: "a la hora",
+    at_minutes: "en el minuto",
+    at_seconds: "en el segundo",
+    only: "solo el",
+    once: "una vez",
+    times: "veces",
+    until: "hasta el",
+    lowercase_names: true,
+    ordinal: |n| match n {
+        1 => "primer".to_string(),
+        2 => "segundo".to_string(),
+        3 => "tercer".to_string(),
+        4 => "cuarto".to_string(),
+        5 => "quinto".to_string(),
+        -1 => "último".to_string(),
+        -2 => "penúltimo".to_string(),
+        n if n < 0 => format!("{}º desde el final", -n),
+        n => format!("{}º", n),
+    },
+    month_day: |n| match n {
+        -1 => "último".to_string(),
+        -2 => "penúltimo".to_string(),
+        n if n < 0 => format!("{}º desde el final", -n),
+        n => n.to_string(),
+    },
+    date: |day, month, year| format!("{} {} {}", day, month, year),
+};
+
+impl RuleWords {
+    fn for_locale(locale_code: &str) -> &'static RuleWords {
+        match locale_code {
+            "es" => &SPANISH_RULE_WORDS,
+            _ => &ENGLISH_RULE_WORDS,
+        }
+    }
+
+    fn name(&self, name: &str) -> String {
+        if self.lowercase_names {
+            name.to_lowercase()
+        } else {
+            name.to_string()
+        }
+    }
+
+    /// Joins items as "a, b and c".
+    fn list(&self, items: &[String]) -> String {
+        match items {
+            [] => String::new(),
+            [only] => only.clone(),
+            [rest @ .., last] => format!("{} {} {}", rest.join(", "), self.and, last),
+        }
+    }
+}
+
+/// Number of consecutive periods without an occurrence after which a rule is
+/// considered exhausted. The Gregorian calendar repeats every 400 years, so a
+/// day-based rule that matches nothing in that span never will.
+fn empty_period_limit(frequency: Frequency) -> u32 {
+    match frequency {
+        Frequency::Yearly => 400,
+        Frequency::Monthly => 4_800,
+        Frequency::Weekly => 20_871,
+        Frequency::Daily => 146_097,
+        Frequency::Hourly | Frequency::Minutely | Frequency::Secondly => 146_097 * 24,
+    }
+}
+
+impl RecurrenceRule {
+    /// Creates a rule with the given frequency, an interval of 1 and no other parts.
+    pub fn new(frequency: Frequency) -> Self {
+        RecurrenceRule {
+            frequency,
+            interval: 1,
+            count: None,
+            until: None,
+            until_is_date: false,
+            by_second: Vec::new(),
+            by_minute: Vec::new(),
+            by_hour: Vec::new(),
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_year_day: Vec::new(),
+            by_week_no: Vec::new(),
+            by_month: Vec::new(),
+            by_set_pos: Vec::new(),
+            week_start: Weekday::Mon,
+        }
+    }
+
+    /// Checks that every part is within its RFC 5545 range and that no parts conflict.
+    pub fn validate(&self) -> Result<(), DateError> {
+        if self.count.is_some() && self.until.is_some() {
+            return Err(conflicting_parts("COUNT", "UNTIL"));
+        }
+        if self.interval == 0 {
+            return Err(invalid_part("INTERVAL", "0"));
+        }
+
+        check_range("BYSECOND", &self.by_second, 0, 59)?;
+        check_range("BYMINUTE", &self.by_minute, 0, 59)?;
+        check_range("BYHOUR", &self.by_hour, 0, 23)?;
+        check_range("BYMONTH", &self.by_month, 1, 12)?;
+        check_signed_range("BYMONTHDAY", &self.by_month_day, 31)?;
+        check_signed_range("BYYEARDAY", &self.by_year_day, 366)?;
+        check_signed_range("BYWEEKNO", &self.by_week_no, 53)?;
+        check_signed_range("BYSETPOS", &self.by_set_pos, 366)?;
+        for entry in &self.by_day {
+            if let Some(ordinal) = entry.ordinal {
+                if ordinal == 0 || ordinal.abs() > 53 {
+                    return Err(invalid_part("BYDAY", &entry.to_string()));
+                }
+            }
+        }
+
+        let frequency = format!("FREQ={}", self.frequency);
+        if !self.by_week_no.is_empty() && self.frequency != Frequency::Yearly {
+            return Err(conflicting_parts("BYWEEKNO", &frequency));
+        }
+        if !self.by_year_day.is_empty()
+            && matches!(
+                self.frequency,
+                Frequency::Daily | Frequency::Weekly | Frequency::Monthly
+            )
+        {
+            return Err(conflicting_parts("BYYEARDAY", &frequency));
+        }
+        if !self.by_month_day.is_empty() && self.frequency == Frequency::Weekly {
+            return Err(conflicting_parts("BYMONTHDAY", &frequency));
+        }
+        if self.by_day.iter().any(|entry| entry.ordinal.is_some()) {
+            match self.frequency {
+                Frequency::Monthly => {}
+                Frequency::Yearly if self.by_week_no.is_empty() => {}
+                Frequency::Yearly => return Err(conflicting_parts("BYDAY", "BYWEEKNO")),
+                _ => return Err(conflicting_parts("BYDAY", &frequency)),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Describes the rule in words, e.g. "every 2 weeks on Monday and Wednesday,
+    /// until Dec 31, 2024". Supports `en` and `es`; other locales use English.
+    pub fn to_text(&self, locale_code: &str) -> String {
+        let locale = LocaleManager::new();
+        let words = RuleWords::for_locale(locale_code);
+        let (unit, units) = words.units[self.frequency_rank() as usize];
+
+        let mut text = match self.interval {
+            0 | 1 => format!("{} {}", words.every, unit),
+            n => format!("{} {} {}", words.every, n, units),
+        };
+        let mut push = |phrase: String| {
+            text.push(' ');
+            text.push_str(&phrase);
+        };
+        let numbers =
+            |values: &[i32]| -> Vec<String> { values.iter().map(i32::to_string).collect() };
+
+        if !self.by_week_no.is_empty() {
+            push(format!(
+                "{} {}",
+                words.in_weeks,
+                words.list(&numbers(&self.by_week_no))
+            ));
+        }
+        if !self.by_year_day.is_empty() {
+            let days = words.list(&numbers(&self.by_year_day));
+            push(words.on_year_days.replace("{days}", &days));
+        }
+        if !self.by_month_day.is_empty() {
+            let days: Vec<String> = self
+                .by_month_day
+                .iter()
+                .map(|n| (words.month_day)(*n))
+                .collect();
+            push(format!("{} {}", words.on_month_days, words.list(&days)));
+        }
+        if !self.by_day.is_empty() {
+            let days: Vec<String> = self
+                .by_day
+                .iter()
+                .map(|entry| {
+                    let name = words.name(locale.weekday_name(locale_code, entry.weekday));
+                    match entry.ordinal {
+                        Some(n) => words
+                            .nth_weekday
+                            .replace("{ordinal}", &(words.ordinal)(n))
+                            .replace("{weekday}", &name),
+                        None => name,
+                    }
+                })
+                .collect();
+            push(format!("{} {}", words.on_weekdays, words.list(&days)));
+        }
+        if !self.by_month.is_empty() {
+            let months: Vec<String> = self
+                .by_month
+                .iter()
+                .map(|m| words.name(locale.month_name(locale_code, *m)))
+                .collect();
+            push(format!("{} {}", words.in_months, words.list(&months)));
+        }
+        // Hours and minutes together read as clock times; otherwise each part is
+        // described on its own rather than inventing the missing ones.
+        let values = |values: &[u32]| -> String {
+            let values: Vec<String> = sorted(values).iter().map(u32::to_string).collect();
+            words.list(&values)
+        };
+        let clock = !self.by_hour.is_empty() && !self.by_minute.is_empty();
+        if clock {
+            let mut times: Vec<String> = Vec::new();
+            for hour in sorted(&self.by_hour) {
+                for minute in sorted(&self.by_minute) {
+                    let time = format!("{:02}:{:02}", hour, minute);
+                    if self.by_second.is_empty() {
+                        times.push(time);
+                    } else {
+                        let seconds = sorted(&self.by_second);
+                        times.extend(seconds.iter().map(|s| format!("{}:{:02}", time, s)));
+                    }
+                }
+            }
+            push(format!("{} {}", words.at_times, words.list(&times)));
+        } else {
+            if !self.by_hour.is_empty() {
+                push(format!("{} {}", words.at_hours, values(&self.by_hour)));
+            }
+            if !self.by_minute.is_empty() {
+                push(format!("{} {}", words.at_minutes, values(&self.by_minute)));
+            }
+            if !self.by_second.is_empty() {
+                push(format!("{} {}", words.at_seconds, values(&self.by_second)));
+            }
+        }
+        if !self.by_set_pos.is_empty() {
+            let positions: Vec<String> = self
+                .by_set_pos
+                .iter()
+                .map(|n| (words.ordinal)(*n))
+                .collect();
+            text.push_str(&format!(", {} {}", words.only, words.list(&positions)));
+        }
+
+        match self.count {
+            Some(1) => text.push_str(&format!(", {}", words.once)),
+            Some(count) => text.push_str(&format!(", {} {}", count, words.times)),
+            None => {}
+        }
+        if let Some(until) = self.until {
+            let month = words.name(locale.month_abbreviation(locale_code, until.month()));
+            let date = (words.date)(until.day(), &month, until.year());
+            text.push_str(&format!(", {} {}", words.until, date));
+        }
+
+        text
+    }
+
+    /// Fills in the parts RFC 5545 derives from the start date when they are absent.
+    fn with_defaults(&self, start: NaiveDateTime) -> RecurrenceRule {
+        let mut rule = self.clone();
+        rule.interval = rule.interval.max(1);
+
+        let no_day_parts = rule.by_week_no.is_empty()
+            && rule.by_year_day.is_empty()
+            && rule.by_month_day.is_empty()
+            && rule.by_day.is_empty();
+        if no_day_parts {
+            match rule.frequency {
+                Frequency::Yearly => {
+                    if rule.by_month.is_empty() {
+                        rule.by_month.push(start.month());
+                    }
+                    rule.by_month_day.push(start.day() as i32);
+                }
+                Frequency::Monthly => rule.by_month_day.push(start.day() as i32),
+                Frequency::Weekly => rule.by_day.push(WeekdayNum::every(start.weekday())),
+                _ => {}
+            }
+        }
+
+        let frequency_rank = rule.frequency_rank();
+        let coarser_than = |frequency: Frequency| frequency_rank > rank(frequency);
+        if rule.by_hour.is_empty() && coarser_than(Frequency::Hourly) {
+            rule.by_hour.push(start.hour());
+        }
+        if rule.by_minute.is_empty() && coarser_than(Frequency::Minutely) {
+            rule.by_minute.push(start.minute());
+        }
+        if rule.by_second.is_empty() && coarser_than(Frequency::Secondly) {
+            rule.by_second.push(start.second());
+        }
+        rule
+    }
+
+    fn frequency_rank(&self) -> u8 {
+        rank(self.frequency)
+    }
+
+    fn is_sub_daily(&self) -> bool {
+        self.frequency_rank() < rank(Frequency::Daily)
+    }
+
+    /// Returns the first instant of the `index`th period counted from `start`.
+    fn period_start(&self, start: NaiveDateTime, index: i64) -> Option<NaiveDateTime> {
+        let step = index.checked_mul(self.interval as i64)?;
+        let midnight = |date: NaiveDate| date.and_hms_opt(0, 0, 0);
+        match self.frequency {
+            Frequency::Yearly => {
+                let year = i32::try_from(start.year() as i64 + step).ok()?;
+                midnight(NaiveDate::from_ymd_opt(year, 1, 1)?)
+            }
+            Frequency::Monthly => {
+                let months = start.year() as i64 * 12 + start.month0() as i64 + step;
+                let year = i32::try_from(months.div_euclid(12)).ok()?;
+                midnight(NaiveDate::from_ymd_opt(
+                    year,
+                    months.rem_euclid(12) as u32 + 1,
+                    1,
+                )?)
+            }
+            Frequency::Weekly => {
+                let date = start.date();
+                let week = date - Duration::days(days_since(date.weekday(), self.week_start));
+                midnight(week.checked_add_signed(Duration::try_weeks(step)?)?)
+            }
+            Frequency::Daily => {
+                midnight(start.date().checked_add_signed(Duration::try_days(step)?)?)
+            }
+            Frequency::Hourly => {
+                let hour = start.date().and_hms_opt(start.hour(), 0, 0)?;
+                hour.checked_add_signed(Duration::try_hours(step)?)
+            }
+            Frequency::Minutely => {
+                let minute = start.date().and_hms_opt(start.hour(), start.minute(), 0)?;
+                minute.checked_add_signed(Duration::try_minutes(step)?)
+            }
+            Frequency::Secondly => {
+                let second = start.with_nanosecond(0)?;
+                second.checked_add_signed(Duration::try_seconds(step)?)
+            }
+        }
+    }
+
+    /// Returns the index of the period containing `target`, which may be negative
+    /// when `target` precedes `start`.
+    fn period_index_of(&self, start: NaiveDateTime, target: NaiveDateTime) -> i64 {
+        let interval = self.interval as i64;
+        let elapsed = match self.frequency {
+            Frequency::Yearly => target.year() as i64 - start.year() as i64,
+            Frequency::Monthly => {
+                (target.year() as i64 - start.year() as i64) * 12 + target.month0() as i64
+                    - start.month0() as i64
+            }
+            Frequency::Weekly => {
+                let week_of = |date: NaiveDate| {
+                    date - Duration::days(days_since(date.weekday(), self.week_start))
+                };
+                (week_of(target.date()) - week_of(start.date())).num_weeks()
+            }
+            Frequency::Daily => (target.date() - start.date()).num_days(),
+            Frequency::Hourly | Frequency::Minutely | Frequency::Secondly => {
+                let unit = match self.frequency {
+                    Frequency::Hourly => 3_600,
+                    Frequency::Minutely => 60,
+                    _ => 1,
+                };
+                let first = match self.period_start(start, 0) {
+                    Some(first) => first,
+                    None => return 0,
+                };
+                return (target - first).num_seconds().div_euclid(unit * interval);
+            }
+        };
+        elapsed.div_euclid(interval)
+    }
+
+    /// For sub-daily rules, returns the index of the first period starting at or
+    /// after `target`, so whole days or hours that cannot match are skipped.
+    fn period_index_at(&self, start: NaiveDateTime, target: NaiveDateTime) -> Option<i64> {
+        let unit = match self.frequency {
+            Frequency::Hourly => 3_600,
+            Frequency::Minutely => 60,
+            Frequency::Secondly => 1,
+            _ => return None,
+        };
+        let first = self.period_start(start, 0)?;
+        let step = unit * self.interval as i64;
+        let elapsed = (target - first).num_seconds();
+        Some((elapsed + step - 1).div_euclid(step))
+    }
+
+    /// Expands one period into its sorted occurrences, applying BYSETPOS.
+    fn expand_period(&self, period: NaiveDateTime, nanosecond: u32) -> Vec<NaiveDateTime> {
+        let times = self.period_times(period, nanosecond);
+        if times.is_empty() {
+            return Vec::new();
+        }
+
+        let mut occurrences: Vec<NaiveDateTime> = self
+            .period_days(period)
+            .into_iter()
+            .filter(|date| self.matches_day(*date))
+            .flat_map(|date| times.iter().map(move |time| date.and_time(*time)))
+            .collect();
+
+        if !self.by_set_pos.is_empty() {
+            let len = occurrences.len() as i32;
+            let mut selected: Vec<NaiveDateTime> = self
+                .by_set_pos
+                .iter()
+                .filter_map(|&pos| {
+                    let index = if pos > 0 { pos - 1 } else { len + pos };
+                    (pos != 0 && (0..len).contains(&index)).then(|| occurrences[index as usize])
+                })
+                .collect();
+            selected.sort();
+            selected.dedup();
+            occurrences = selected;
+        }
+
+        occurrences
+    }
+
+    /// Returns every date covered by the period.
+    fn period_days(&self, period: NaiveDateTime) -> Vec<NaiveDate> {
+        let first = period.date();
+        let len = match self.frequency {
+            Frequency::Yearly => days_in_year(first.year()),
+            Frequency::Monthly => days_in_month(first.year(), first.month()),
+            Frequency::Weekly => 7,
+            _ => 1,
+        };
+        first.iter_days().take(len as usize).collect()
+    }
+
+    /// Returns the sorted times of day at which the period can produce occurrences.
+    fn period_times(&self, period: NaiveDateTime, nanosecond: u32) -> Vec<NaiveTime> {
+        let allows = |list: &[u32], value: u32| list.is_empty() || list.contains(&value);
+        let hours: Vec<u32> = match self.frequency {
+            Frequency::Hourly | Frequency::Minutely | Frequency::Secondly => {
+                if !allows(&self.by_hour, period.hour()) {
+                    return Vec::new();
+                }
+                vec![period.hour()]
+            }
+            _ => sorted(&self.by_hour),
+        };
+        let minutes: Vec<u32> = match self.frequency {
+            Frequency::Minutely | Frequency::Secondly => {
+                if !allows(&self.by_minute, period.minute()) {
+                    return Vec::new();
+                }
+                vec![period.minute()]
+            }
+            _ => sorted(&self.by_minute),
+        };
+        let seconds: Vec<u32> = match self.frequency {
+            Frequency::Secondly => {
+                if !allows(&self.by_second, period.second()) {
+                    return Vec::new();
+                }
+                vec![period.second()]
+            }
+            _ => sorted(&self.by_second),
+        };
+
+        let mut times = Vec::new();
+        for hour in &hours {
+            for minute in &minutes {
+                for second in &seconds {
+                    if let Some(time) =
+                        NaiveTime::from_hms_nano_opt(*hour, *minute, *second, nanosecond)
+                    {
+                        times.push(time);
+                    }
+                }
+            }
+        }
+        times
+    }
+
+    /// Checks the date against BYMONTH, BYWEEKNO, BYYEARDAY, BYMONTHDAY and BYDAY.
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        if !self.by_month.is_empty() && !self.by_month.contains(&date.month()) {
+            return false;
+        }
+
+        if !self.by_week_no.is_empty() {
+            let (week, weeks_in_year) = week_number(date, self.week_start);
+            let negative = week as i32 - weeks_in_year as i32 - 1;
+            if !self
+                .by_week_no
+                .iter()
+                .any(|&n| n == week as i32 || n == negative)
+            {
+                return false;
+            }
+        }
+
+        if !self.by_year_day.is_empty() {
+            let day = date.ordinal() as i32;
+            let negative = day - days_in_year(date.year()) as i32 - 1;
+            if !self.by_year_day.iter().any(|&n| n == day || n == negative) {
+                return false;
+            }
+        }
+
+        if !self.by_month_day.is_empty() {
+            let day = date.day() as i32;
+            let negative = day - days_in_month(date.year(), date.month()) as i32 - 1;
+            if !self.by_month_day.iter().any(|&n| n == day || n == negative) {
+                return false;
+            }
+        }
+
+        if !self.by_day.is_empty()
+            && !self
+                .by_day
+                .iter()
+                .any(|entry| self.matches_weekday(date, entry))
+        {
+            return false;
+        }
+
+        true
+    }
+
+    /// Checks a single BYDAY entry. Ordinals count within the month for monthly
+    /// rules (and yearly rules with BYMONTH) and within the year for other yearly
+    /// rules; they are ignored for finer frequencies.
+    fn matches_weekday(&self, date: NaiveDate, entry: &WeekdayNum) -> bool {
+        if date.weekday() != entry.weekday {
+            return false;
+        }
+        let ordinal = match entry.ordinal {
+            Some(ordinal) => ordinal,
+            None => return true,
+        };
+        let (first, last) = match self.frequency {
+            Frequency::Monthly => month_bounds(date),
+            Frequency::Yearly if !self.by_month.is_empty() => month_bounds(date),
+            Frequency::Yearly => (
+                NaiveDate::from_ymd_opt(date.year(), 1, 1).unwrap(),
+                NaiveDate::from_ymd_opt(date.year(), 12, 31).unwrap(),
+            ),
+            _ => return true,
+        };
+        let from_start = (date - first).num_days() as i32 / 7 + 1;
+        let from_end = -((last - date).num_days() as i32 / 7 + 1);
+        ordinal == from_start || ordinal == from_end
+    }
+}
+
+impl FromStr for RecurrenceRule {
+    type Err = DateError;
+
+    /// Parses RFC 5545 RRULE text such as `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE`.
+    /// A leading `RRULE:` is accepted and part names are case-insensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let body = match s.get(..6) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("RRULE:") => &s[6..],
+            _ => s,
+        };
+
+        let mut parts: Vec<(String, &str)> = Vec::new();
+        for part in body.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+            let (name, value) = part.split_once('=').ok_or_else(|| invalid_part(part, ""))?;
+            let name = name.trim().to_ascii_uppercase();
+            if parts.iter().any(|(seen, _)| *seen == name) {
+                return Err(DateError::DuplicateRulePart(name));
+            }
+            parts.push((name, value.trim()));
+        }
+
+        let frequency = parts
+            .iter()
+            .find(|(name, _)| name == "FREQ")
+            .ok_or(DateError::MissingRulePart("FREQ"))?
+            .1
+            .parse::<Frequency>()?;
+        let mut rule = RecurrenceRule::new(frequency);
+
+        for (name, value) in parts {
+            match name.as_str() {
+                "FREQ" => {}
+                "INTERVAL" => rule.interval = parse_value(&name, value)?,
+                "COUNT" => rule.count = Some(parse_value(&name, value)?),
+                "UNTIL" => {
+                    let (until, is_date) = parse_until(value)?;
+                    rule.until = Some(until);
+                    rule.until_is_date = is_date;
+                }
+                "BYSECOND" => rule.by_second = parse_list(&name, value)?,
+                "BYMINUTE" => rule.by_minute = parse_list(&name, value)?,
+                "BYHOUR" => rule.by_hour = parse_list(&name, value)?,
+                "BYDAY" => rule.by_day = parse_list(&name, value)?,
+                "BYMONTHDAY" => rule.by_month_day = parse_list(&name, value)?,
+                "BYYEARDAY" => rule.by_year_day = parse_list(&name, value)?,
+                "BYWEEKNO" => rule.by_week_no = parse_list(&name, value)?,
+                "BYMONTH" => rule.by_month = parse_list(&name, value)?,
+                "BYSETPOS" => rule.by_set_pos = parse_list(&name, value)?,
+                "WKST" => {
+                    rule.week_start =
+                        parse_weekday_code(value).ok_or_else(|| invalid_part(&name, value))?
+                }
+                _ => return Err(DateError::UnknownRulePart(name)),
+            }
+        }
+
+        rule.validate()?;
+        Ok(rule)
+    }
+}
+
+impl fmt::Display for RecurrenceRule {
+    /// Writes the rule as RRULE text (without the `RRULE:` prefix).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FREQ={}", self.frequency)?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        write_list(f, "BYMONTH", &self.by_month)?;
+        write_list(f, "BYWEEKNO", &self.by_week_no)?;
+        write_list(f, "BYYEARDAY", &self.by_year_day)?;
+        write_list(f, "BYMONTHDAY", &self.by_month_day)?;
+        write_list(f, "BYDAY", &self.by_day)?;
+        write_list(f, "BYHOUR", &self.by_hour)?;
+        write_list(f, "BYMINUTE", &self.by_minute)?;
+        write_list(f, "BYSECOND", &self.by_second)?;
+        write_list(f, "BYSETPOS", &self.by_set_pos)?;
+        if self.week_start != Weekday::Mon {
+            write!(f, ";WKST={}", weekday_code(self.week_start))?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        if let Some(until) = self.until {
+            let format = if self.until_is_date {
+                "%Y%m%d"
+            } else {
+                "%Y%m%dT%H%M%SZ"
+            };
+            write!(f, ";UNTIL={}", until.format(format))?;
+        }
+        Ok(())
+    }
+}
+
+/// Lazily yields the occurrences of a rule, one RFC 5545 period at a time.
+///
+/// Rules without `count` or `until` are unbounded; combine them with
+/// `take` or `take_while`.
+///
+/// The rule is expanded in the wall-clock time of the start date's zone (UTC
+/// for [`RecurrenceGenerator::new`]), so a daily 09:00 rule stays at 09:00 local
+/// time across DST changes. Local times skipped by a transition are shifted
+/// forward and repeated ones use the first instant, as RFC 5545 specifies.
 pub struct RecurrenceGenerator {
     rule: RecurrenceRule,
-    start_date: DateTime<Utc>,
-    occurrences: VecDeque<DateTime<Utc>>,
+    start_date: DateTime<Tz>,
+    period_index: i64,
+    pending: VecDeque<NaiveDateTime>,
+    emitted: u32,
+    last: Option<DateTime<Utc>>,
+    finished: bool,
 }
 
 impl RecurrenceGenerator {
-    /// Creates a new RecurrenceGenerator.
+    /// Creates a new RecurrenceGenerator expanding in UTC. No occurrences are
+    /// computed until iteration.
     pub fn new(start_date: DateTime<Utc>, rule: RecurrenceRule) -> Self {
-        let mut generator = RecurrenceGenerator {
-            rule,
+        Self::from_start(start_date.with_timezone(&Tz::UTC), rule)
+    }
+
+    /// Creates a RecurrenceGenerator expanding in the time zone of `start_date`.
+    pub fn in_timezone(start_date: &ZonedDateX, rule: RecurrenceRule) -> Self {
+        Self::from_start(start_date.datetime, rule)
+    }
+
+    fn from_start(start_date: DateTime<Tz>, rule: RecurrenceRule) -> Self {
+        RecurrenceGenerator {
+            rule: rule.with_defaults(start_date.naive_local()),
             start_date,
-            occurrences: VecDeque::new(),
+            period_index: 0,
+            pending: VecDeque::new(),
+            emitted: 0,
+            last: None,
+            finished: false,
+        }
+    }
+
+    /// Converts a local occurrence to its instant.
+    fn resolve(&self, local: NaiveDateTime) -> DateTime<Utc> {
+        let tz = self.start_date.timezone();
+        TimeZoneHandler::resolve_local(tz, local, LocalTimePolicy::ShiftForward)
+            .map(|datetime| datetime.with_timezone(&Utc))
+            .unwrap_or_else(|_| local.and_utc())
+    }
+
+    /// Returns the wall-clock time of `date` in the generator's zone.
+    fn local(&self, date: &DateX) -> NaiveDateTime {
+        date.datetime
+            .with_timezone(&self.start_date.timezone())
+            .naive_local()
+    }
+
+    /// Returns the occurrences between `start` and `end`, including the bounds
+    /// when `inclusive` is set.
+    ///
+    /// Queries always consider the whole rule, regardless of how far this
+    /// generator has been iterated. Rules without COUNT jump straight to the
+    /// period containing the window.
+    pub fn between(&self, start: &DateX, end: &DateX, inclusive: bool) -> Vec<DateX> {
+        self.starting_at(start)
+            .skip_while(|occurrence| occurrence < start || (!inclusive && occurrence == start))
+            .take_while(|occurrence| occurrence < end || (inclusive && occurrence == end))
+            .collect()
+    }
+
+    /// Returns the first occurrence strictly after `date`.
+    pub fn after(&self, date: &DateX) -> Option<DateX> {
+        self.starting_at(date).find(|occurrence| occurrence > date)
+    }
+
+    /// Returns the last occurrence strictly before `date`.
+    pub fn before(&self, date: &DateX) -> Option<DateX> {
+        if self.rule.count.is_some() {
+            return self
+                .starting_at(date)
+                .take_while(|occurrence| occurrence < date)
+                .last();
+        }
+
+        // Without COUNT every period stands on its own, so walk backwards from
+        // the period containing `date`, or UNTIL when that is earlier, until one
+        // has an earlier occurrence.
+        let (end, inclusive) = match self.rule.until {
+            Some(until) if until < date.datetime => (until, true),
+            _ => (date.datetime, false),
+        };
+        let start = self.start_date.naive_local();
+        let mut index = self
+            .rule
+            .period_index_of(start, self.local(&DateX { datetime: end }));
+        let mut empty_periods = 0;
+        while index >= 0 && empty_periods < empty_period_limit(self.rule.frequency) {
+            let period = self.rule.period_start(start, index)?;
+            let latest = self
+                .rule
+                .expand_period(period, start.nanosecond())
+                .into_iter()
+                .map(|occurrence| self.resolve(occurrence))
+                .rfind(|occurrence| {
+                    *occurrence >= self.start_date
+                        && (*occurrence < end || (inclusive && *occurrence == end))
+                });
+            if let Some(datetime) = latest {
+                return Some(DateX { datetime });
+            }
+            empty_periods += 1;
+            index -= 1;
+        }
+        None
+    }
+
+    /// Returns a fresh generator positioned at the period containing `date`.
+    /// Rules with COUNT are counted from the start, so they begin at period 0.
+    fn starting_at(&self, date: &DateX) -> RecurrenceGenerator {
+        let period_index = match self.rule.count {
+            Some(_) => 0,
+            None => self
+                .rule
+                .period_index_of(self.start_date.naive_local(), self.local(date))
+                .max(0),
+        };
+        RecurrenceGenerator {
+            rule: self.rule.clone(),
+            start_date: self.start_date,
+            period_index,
+            pending: VecDeque::new(),
+            emitted: 0,
+            last: None,
+            finished: false,
+        }
+    }
+
+    /// Expands periods until one yields occurrences at or after the start date.
+    /// Returns `false` once the rule is exhausted.
+    fn fill_next_period(&mut self) -> bool {
+        let start = self.start_date.naive_local();
+        let nanosecond = start.nanosecond();
+        let mut empty_periods = 0;
+
+        while let Some(period) = self.rule.period_start(start, self.period_index) {
+            if empty_periods >= empty_period_limit(self.rule.frequency) {
+                return false;
+            }
+
+            if let Some(next) = self.skip_period(start, period) {
+                self.period_index = next;
+                empty_periods += 1;
+                continue;
+            }
+
+            self.pending.extend(
+                self.rule
+                    .expand_period(period, nanosecond)
+                    .into_iter()
+                    .filter(|occurrence| *occurrence >= start),
+            );
+            self.period_index += 1;
+
+            if !self.pending.is_empty() {
+                return true;
+            }
+            empty_periods += 1;
+        }
+
+        false
+    }
+
+    /// For sub-daily rules, returns the index of a later period when the whole
+    /// day or hour of `period` is excluded by the BYxxx parts.
+    fn skip_period(&self, start: NaiveDateTime, period: NaiveDateTime) -> Option<i64> {
+        if !self.rule.is_sub_daily() {
+            return None;
+        }
+        let target = if !self.rule.matches_day(period.date()) {
+            period.date().succ_opt()?.and_hms_opt(0, 0, 0)?
+        } else if !self.rule.by_hour.is_empty() && !self.rule.by_hour.contains(&period.hour()) {
+            period.date().and_hms_opt(period.hour(), 0, 0)? + Duration::hours(1)
+        } else {
+            return None;
         };
-        generator.generate_occurrences();
-        generator
+        self.rule
+            .period_index_at(start, target)
+            .filter(|next| *next > self.period_index)
     }
+}
 
-    /// Generates occurrences based on the rule.
-    fn generate_occurrences(&mut self) {
-        let mut current_date = self.start_date;
-        let mut occurrences_generated = 0;
+impl Iterator for RecurrenceGenerator {
+    type Item = DateX;
 
-        loop {
+    fn next(&mut self) -> Option<DateX> {
+        while !self.finished {
             if let Some(count) = self.rule.count {
-                if occurrences_generated >= count {
+                if self.emitted >= count {
                     break;
                 }
             }
 
+            let occurrence = match self.pending.pop_front() {
+                Some(occurrence) => self.resolve(occurrence),
+                None => {
+                    if !self.fill_next_period() {
+                        break;
+                    }
+                    continue;
+                }
+            };
+
             if let Some(until) = self.rule.until {
-                if current_date > until {
+                if occurrence > until {
                     break;
                 }
             }
 
-            self.occurrences.push_back(current_date);
+            // A time shifted out of a DST gap can land on an instant that was
+            // already produced; it is the same occurrence, not a new one.
+            if self.last.is_some_and(|last| occurrence <= last) {
+                continue;
+            }
 
-            current_date = match self.rule.frequency {
-                Frequency::Secondly => current_date + Duration::seconds(self.rule.interval as i64),
-                Frequency::Minutely => current_date + Duration::minutes(self.rule.interval as i64),
-                Frequency::Hourly => current_date + Duration::hours(self.rule.interval as i64),
-                Frequency::Daily => current_date + Duration::days(self.rule.interval as i64),
-                Frequency::Weekly => current_date + Duration::weeks(self.rule.interval as i64),
-                Frequency::Monthly => {
-                    // For simplicity, add 30 days per month
-                    current_date + Duration::days(30 * self.rule.interval as i64)
-                }
-                Frequency::Yearly => {
-                    // For simplicity, add 365 days per year
-                    current_date + Duration::days(365 * self.rule.interval as i64)
-                }
-            };
+            self.emitted += 1;
+            self.last = Some(occurrence);
+            return Some(DateX {
+                datetime: occurrence,
+            });
+        }
+
+        self.finished = true;
+        self.pending.clear();
+        None
+    }
+}
+
+impl FusedIterator for RecurrenceGenerator {}
 
-            occurrences_generated += 1;
+/// A recurrence set: the union of RRULEs and RDATEs minus the union of EXRULEs
+/// and EXDATEs, as in RFC 5545.
+///
+/// All rules are expanded from the same start date, in its time zone. As with
+/// [`RecurrenceGenerator`], the start date itself is only an occurrence when a
+/// rule produces it or it is added as an RDATE.
+#[derive(Debug, Clone)]
+pub struct RecurrenceSet {
+    start_date: DateTime<Tz>,
+    rrules: Vec<RecurrenceRule>,
+    exrules: Vec<RecurrenceRule>,
+    rdates: Vec<DateX>,
+    exdates: Vec<DateX>,
+}
+
+impl RecurrenceSet {
+    /// Creates an empty recurrence set anchored at the given start date, expanding in UTC.
+    pub fn new(start_date: DateTime<Utc>) -> Self {
+        Self::from_start(start_date.with_timezone(&Tz::UTC))
+    }
+
+    /// Creates an empty recurrence set expanding in the time zone of `start_date`.
+    pub fn in_timezone(start_date: &ZonedDateX) -> Self {
+        Self::from_start(start_date.datetime)
+    }
+
+    fn from_start(start_date: DateTime<Tz>) -> Self {
+        RecurrenceSet {
+            start_date,
+            rrules: Vec::new(),
+            exrules: Vec::new(),
+            rdates: Vec::new(),
+            exdates: Vec::new(),
         }
     }
 
-    /// Returns the next occurrence.
-    pub fn next(&mut self) -> Option<DateTime<Utc>> {
-        self.occurrences.pop_front()
+    /// Adds a rule whose occurrences are included.
+    pub fn add_rrule(&mut self, rule: RecurrenceRule) {
+        self.rrules.push(rule);
+    }
+
+    /// Adds a rule whose occurrences are excluded.
+    pub fn add_exrule(&mut self, rule: RecurrenceRule) {
+        self.exrules.push(rule);
+    }
+
+    /// Adds a single included date.
+    pub fn add_rdate(&mut self, date: DateX) {
+        self.rdates.push(date);
+    }
+
+    /// Adds a single excluded date.
+    pub fn add_exdate(&mut self, date: DateX) {
+        self.exdates.push(date);
+    }
+
+    /// Returns a lazy iterator over the sorted, de-duplicated occurrences of the set.
+    pub fn iter(&self) -> RecurrenceSetIter {
+        let generators = |rules: &[RecurrenceRule]| -> Vec<Peekable<RecurrenceGenerator>> {
+            rules
+                .iter()
+                .map(|rule| {
+                    RecurrenceGenerator::from_start(self.start_date, rule.clone()).peekable()
+                })
+                .collect()
+        };
+        let sorted_dates = |dates: &[DateX]| -> VecDeque<DateX> {
+            let mut dates = dates.to_vec();
+            dates.sort();
+            dates.dedup();
+            dates.into()
+        };
+
+        RecurrenceSetIter {
+            includes: generators(&self.rrules),
+            excludes: generators(&self.exrules),
+            rdates: sorted_dates(&self.rdates),
+            exdates: sorted_dates(&self.exdates),
+        }
+    }
+}
+
+impl IntoIterator for &RecurrenceSet {
+    type Item = DateX;
+    type IntoIter = RecurrenceSetIter;
+
+    fn into_iter(self) -> RecurrenceSetIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the occurrences of a [`RecurrenceSet`], merging its rules lazily.
+pub struct RecurrenceSetIter {
+    includes: Vec<Peekable<RecurrenceGenerator>>,
+    excludes: Vec<Peekable<RecurrenceGenerator>>,
+    rdates: VecDeque<DateX>,
+    exdates: VecDeque<DateX>,
+}
+
+impl RecurrenceSetIter {
+    /// Removes and returns the earliest included date, consuming every source
+    /// that produces the same date so duplicates collapse into one.
+    fn next_included(&mut self) -> Option<DateX> {
+        let earliest = self
+            .includes
+            .iter_mut()
+            .filter_map(|generator| generator.peek().cloned())
+            .chain(self.rdates.front().cloned())
+            .min()?;
+
+        for generator in &mut self.includes {
+            generator.next_if_eq(&earliest);
+        }
+        if self.rdates.front() == Some(&earliest) {
+            self.rdates.pop_front();
+        }
+        Some(earliest)
+    }
+
+    /// Checks whether the date is excluded, discarding exclusions that lie before it.
+    fn is_excluded(&mut self, date: &DateX) -> bool {
+        while self.exdates.front().is_some_and(|exdate| exdate < date) {
+            self.exdates.pop_front();
+        }
+        let mut excluded = self.exdates.front() == Some(date);
+
+        for generator in &mut self.excludes {
+            while generator.next_if(|occurrence| occurrence < date).is_some() {}
+            excluded |= generator.peek() == Some(date);
+        }
+        excluded
+    }
+}
+
+impl Iterator for RecurrenceSetIter {
+    type Item = DateX;
+
+    fn next(&mut self) -> Option<DateX> {
+        while let Some(date) = self.next_included() {
+            if !self.is_excluded(&date) {
+                return Some(date);
+            }
+        }
+        None
+    }
+}
+
+fn invalid_part(part: &str, value: &str) -> DateError {
+    DateError::InvalidRulePart {
+        part: part.to_string(),
+        value: value.to_string(),
+    }
+}
+
+fn conflicting_parts(first: &str, second: &str) -> DateError {
+    DateError::ConflictingRuleParts {
+        first: first.to_string(),
+        second: second.to_string(),
+    }
+}
+
+fn check_range(part: &str, values: &[u32], min: u32, max: u32) -> Result<(), DateError> {
+    match values.iter().find(|v| !(min..=max).contains(*v)) {
+        Some(value) => Err(invalid_part(part, &value.to_string())),
+        None => Ok(()),
+    }
+}
+
+fn check_signed_range(part: &str, values: &[i32], max: i32) -> Result<(), DateError> {
+    match values.iter().find(|v| **v == 0 || v.abs() > max) {
+        Some(value) => Err(invalid_part(part, &value.to_string())),
+        None => Ok(()),
+    }
+}
+
+fn parse_value<T: FromStr>(part: &str, value: &str) -> Result<T, DateError> {
+    value.trim().parse().map_err(|_| invalid_part(part, value))
+}
+
+fn parse_list<T: FromStr>(part: &str, value: &str) -> Result<Vec<T>, DateError> {
+    value
+        .split(',')
+        .map(|item| parse_value(part, item))
+        .collect()
+}
+
+/// Parses an UNTIL value: a date (`20241231`) or a date-time (`20241231T000000Z`).
+/// Floating date-times without `Z` are taken as UTC. Also returns whether the
+/// value was a date.
+fn parse_until(value: &str) -> Result<(DateTime<Utc>, bool), DateError> {
+    let error = || invalid_part("UNTIL", value);
+    let (datetime, is_date) = match value.strip_suffix('Z').unwrap_or(value) {
+        date if date.len() == 8 => {
+            let date = NaiveDate::parse_from_str(date, "%Y%m%d").map_err(|_| error())?;
+            (date.and_time(NaiveTime::MIN), true)
+        }
+        datetime => (
+            NaiveDateTime::parse_from_str(datetime, "%Y%m%dT%H%M%S").map_err(|_| error())?,
+            false,
+        ),
+    };
+    Ok((datetime.and_utc(), is_date))
+}
+
+fn write_list<T: fmt::Display>(
+    f: &mut fmt::Formatter<'_>,
+    part: &str,
+    values: &[T],
+) -> fmt::Result {
+    if values.is_empty() {
+        return Ok(());
+    }
+    write!(f, ";{}=", part)?;
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            write!(f, ",")?;
+        }
+        write!(f, "{}", value)?;
+    }
+    Ok(())
+}
+
+fn weekday_code(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+fn parse_weekday_code(code: &str) -> Option<Weekday> {
+    match code.trim().to_ascii_uppercase().as_str() {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn rank(frequency: Frequency) -> u8 {
+    match frequency {
+        Frequency::Secondly => 0,
+        Frequency::Minutely => 1,
+        Frequency::Hourly => 2,
+        Frequency::Daily => 3,
+        Frequency::Weekly => 4,
+        Frequency::Monthly => 5,
+        Frequency::Yearly => 6,
+    }
+}
+
+fn sorted(values: &[u32]) -> Vec<u32> {
+    let mut values = values.to_vec();
+    values.sort_unstable();
+    values.dedup();
+    values
+}
+
+/// Days from the week start to the given weekday (0-6).
+fn days_since(weekday: Weekday, week_start: Weekday) -> i64 {
+    (weekday.num_days_from_monday() as i64 - week_start.num_days_from_monday() as i64).rem_euclid(7)
+}
+
+fn days_in_year(year: i32) -> u32 {
+    if crate::utils::is_leap_year(year) {
+        366
+    } else {
+        365
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    crate::utils::days_in_month(year, month)
+}
+
+fn month_bounds(date: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let first = date.with_day(1).unwrap();
+    let last = date
+        .with_day(days_in_month(date.year(), date.month()))
+        .unwrap();
+    (first, last)
+}
+
+/// Returns the first day of week 1 of `year`: the first week, starting on
+/// `week_start`, that has at least four days in the year.
+fn week_one_start(year: i32, week_start: Weekday) -> NaiveDate {
+    let jan1 = NaiveDate::from_ymd_opt(year, 1, 1).unwrap();
+    let offset = days_since(jan1.weekday(), week_start);
+    if offset <= 3 {
+        jan1 - Duration::days(offset)
+    } else {
+        jan1 + Duration::days(7 - offset)
+    }
+}
+
+/// Returns the RFC 5545 week number of `date` and the number of weeks in its week-numbering year.
+fn week_number(date: NaiveDate, week_start: Weekday) -> (u32, u32) {
+    let mut year = date.year();
+    if date < week_one_start(year, week_start) {
+        year -= 1;
+    } else if date >= week_one_start(year + 1, week_start) {
+        year += 1;
+    }
+    let first = week_one_start(year, week_start);
+    let week = (date - first).num_days() / 7 + 1;
+    let weeks = (week_one_start(year + 1, week_start) - first).num_days() / 7;
+    (week as u32, weeks as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> DateX {
+        DateX::parse(text, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn generator(start: &str, rule: &str) -> RecurrenceGenerator {
+        RecurrenceGenerator::new(date(start).datetime, rule.parse().unwrap())
+    }
+
+    fn occurrences(start: &str, rule: &str, limit: usize) -> Vec<String> {
+        generator(start, rule)
+            .take(limit)
+            .map(|occurrence| occurrence.format("%Y-%m-%d"))
+            .collect()
+    }
+
+    #[test]
+    fn every_other_week_on_several_days() {
+        let dates = occurrences(
+            "1997-09-01 09:00",
+            "FREQ=WEEKLY;INTERVAL=2;UNTIL=19971224T000000Z;WKST=SU;BYDAY=MO,WE,FR",
+            100,
+        );
+        assert_eq!(dates.len(), 25);
+        assert_eq!(
+            dates[..7],
+            [
+                "1997-09-01",
+                "1997-09-03",
+                "1997-09-05",
+                "1997-09-15",
+                "1997-09-17",
+                "1997-09-19",
+                "1997-09-29"
+            ]
+        );
+        assert_eq!(dates[24], "1997-12-22");
+    }
+
+    #[test]
+    fn ordinal_weekdays() {
+        assert_eq!(
+            occurrences("1997-09-05 09:00", "FREQ=MONTHLY;COUNT=10;BYDAY=1FR", 100)[..5],
+            [
+                "1997-09-05",
+                "1997-10-03",
+                "1997-11-07",
+                "1997-12-05",
+                "1998-01-02"
+            ]
+        );
+        assert_eq!(
+            occurrences("1997-09-22 09:00", "FREQ=MONTHLY;COUNT=6;BYDAY=-2MO", 100),
+            [
+                "1997-09-22",
+                "1997-10-20",
+                "1997-11-17",
+                "1997-12-22",
+                "1998-01-19",
+                "1998-02-16"
+            ]
+        );
+        assert_eq!(
+            occurrences("2024-01-01 09:00", "FREQ=MONTHLY;BYDAY=-1FR", 3),
+            ["2024-01-26", "2024-02-23", "2024-03-29"]
+        );
+        assert_eq!(
+            occurrences("2024-01-01 09:00", "FREQ=YEARLY;BYMONTH=3;BYDAY=2SU", 3),
+            ["2024-03-10", "2025-03-09", "2026-03-08"]
+        );
+    }
+
+    #[test]
+    fn set_positions() {
+        assert_eq!(
+            occurrences(
+                "1997-09-29 09:00",
+                "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1",
+                4
+            ),
+            ["1997-09-30", "1997-10-31", "1997-11-28", "1997-12-31"]
+        );
+        assert_eq!(
+            occurrences(
+                "1997-09-04 09:00",
+                "FREQ=MONTHLY;COUNT=3;BYDAY=TU,WE,TH;BYSETPOS=3",
+                100
+            ),
+            ["1997-09-04", "1997-10-07", "1997-11-06"]
+        );
+    }
+
+    #[test]
+    fn week_numbers_depend_on_the_week_start() {
+        assert_eq!(
+            occurrences("1997-05-12 09:00", "FREQ=YEARLY;BYWEEKNO=20;BYDAY=MO", 3),
+            ["1997-05-12", "1998-05-11", "1999-05-17"]
+        );
+        assert_eq!(
+            occurrences(
+                "1997-05-12 09:00",
+                "FREQ=YEARLY;BYWEEKNO=20;BYDAY=MO;WKST=SU",
+                3
+            ),
+            ["1997-05-12", "1998-05-18", "1999-05-17"]
+        );
+        assert_eq!(
+            occurrences(
+                "1997-08-05 09:00",
+                "FREQ=WEEKLY;INTERVAL=2;COUNT=4;BYDAY=TU,SU;WKST=MO",
+                100
+            ),
+            ["1997-08-05", "1997-08-10", "1997-08-19", "1997-08-24"]
+        );
+        assert_eq!(
+            occurrences(
+                "1997-08-05 09:00",
+                "FREQ=WEEKLY;INTERVAL=2;COUNT=4;BYDAY=TU,SU;WKST=SU",
+                100
+            ),
+            ["1997-08-05", "1997-08-17", "1997-08-19", "1997-08-31"]
+        );
+    }
+
+    #[test]
+    fn negative_year_days() {
+        assert_eq!(
+            occurrences("2023-01-01 09:00", "FREQ=YEARLY;BYYEARDAY=-1", 2),
+            ["2023-12-31", "2024-12-31"]
+        );
+        assert_eq!(
+            occurrences("2023-01-01 09:00", "FREQ=YEARLY;BYYEARDAY=-366", 2),
+            ["2024-01-01", "2028-01-01"]
+        );
+        assert_eq!(
+            occurrences("2023-01-01 09:00", "FREQ=YEARLY;BYYEARDAY=1,-306", 4),
+            ["2023-01-01", "2023-03-01", "2024-01-01", "2024-03-01"]
+        );
+    }
+
+    #[test]
+    fn missing_month_days_are_skipped() {
+        assert_eq!(
+            occurrences("2024-01-31 09:00", "FREQ=MONTHLY;COUNT=4", 100),
+            ["2024-01-31", "2024-03-31", "2024-05-31", "2024-07-31"]
+        );
+        assert_eq!(
+            occurrences("2024-02-29 09:00", "FREQ=YEARLY;COUNT=3", 100),
+            ["2024-02-29", "2028-02-29", "2032-02-29"]
+        );
+    }
+
+    #[test]
+    fn unbounded_rules_are_generated_lazily() {
+        let mut weekly = generator("2024-03-04 09:00", "FREQ=WEEKLY;BYDAY=MO");
+        assert_eq!(weekly.next(), Some(date("2024-03-04 09:00")));
+        assert_eq!(weekly.nth(99), Some(date("2026-02-02 09:00")));
+
+        let mut yearly = generator("2024-01-01 00:00", "FREQ=YEARLY");
+        assert_eq!(yearly.nth(1000), Some(date("3024-01-01 00:00")));
+    }
+
+    #[test]
+    fn finished_generators_stay_finished() {
+        let mut rule = generator("2024-03-04 09:00", "FREQ=DAILY;COUNT=2");
+        assert_eq!(rule.by_ref().count(), 2);
+        assert_eq!(rule.next(), None);
+        assert_eq!(rule.next(), None);
+
+        // February 30th never exists, so the rule is exhausted rather than looping.
+        let mut never = generator("2024-01-01 00:00", "FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30");
+        assert_eq!(never.next(), None);
+    }
+
+    #[test]
+    fn range_queries() {
+        let weekly = generator("2024-03-04 09:00", "FREQ=WEEKLY;BYDAY=MO");
+        let (start, end) = (date("2024-03-11 09:00"), date("2024-03-25 09:00"));
+        assert_eq!(
+            weekly.between(&start, &end, true),
+            [start.clone(), date("2024-03-18 09:00"), end.clone()]
+        );
+        assert_eq!(
+            weekly.between(&start, &end, false),
+            [date("2024-03-18 09:00")]
+        );
+        assert_eq!(weekly.after(&start), Some(date("2024-03-18 09:00")));
+        assert_eq!(weekly.before(&start), Some(date("2024-03-04 09:00")));
+    }
+
+    #[test]
+    fn sets_combine_rules_and_dates() {
+        let mut set = RecurrenceSet::new(date("2024-03-04 09:00").datetime);
+        set.add_rrule("FREQ=WEEKLY;BYDAY=MO;COUNT=6".parse().unwrap());
+        set.add_exrule("FREQ=MONTHLY;BYDAY=1MO".parse().unwrap());
+        set.add_rdate(date("2024-03-13 12:00"));
+        set.add_rdate(date("2024-03-11 09:00"));
+        set.add_exdate(date("2024-03-18 09:00"));
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            [
+                date("2024-03-11 09:00"),
+                date("2024-03-13 12:00"),
+                date("2024-03-25 09:00"),
+                date("2024-04-08 09:00")
+            ]
+        );
+    }
+
+    #[test]
+    fn sets_merge_rules_in_order() {
+        let mut set = RecurrenceSet::new(date("2024-03-01 09:00").datetime);
+        set.add_rrule("FREQ=WEEKLY;BYDAY=FR".parse().unwrap());
+        set.add_rrule("FREQ=MONTHLY;BYMONTHDAY=1,15".parse().unwrap());
+        let dates: Vec<String> = set.iter().take(5).map(|d| d.format("%Y-%m-%d")).collect();
+        assert_eq!(
+            dates,
+            [
+                "2024-03-01",
+                "2024-03-08",
+                "2024-03-15",
+                "2024-03-22",
+                "2024-03-29"
+            ]
+        );
+    }
+
+    fn new_york(rule: &str, local: &str) -> Vec<String> {
+        let naive = NaiveDateTime::parse_from_str(local, "%Y-%m-%d %H:%M").unwrap();
+        let start =
+            ZonedDateX::from_local(naive, "America/New_York", LocalTimePolicy::Reject).unwrap();
+        RecurrenceGenerator::in_timezone(&start, rule.parse().unwrap())
+            .map(|d| {
+                let local = d.to_timezone("America/New_York").unwrap();
+                local.format("%Y-%m-%d %H:%M %z")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn local_times_in_a_gap_shift_forward() {
+        assert_eq!(
+            new_york("FREQ=DAILY;COUNT=4", "2024-03-08 02:30"),
+            [
+                "2024-03-08 02:30 -0500",
+                "2024-03-09 02:30 -0500",
+                "2024-03-10 03:30 -0400",
+                "2024-03-11 02:30 -0400"
+            ]
+        );
+    }
+
+    #[test]
+    fn repeated_local_times_use_the_first_instant() {
+        assert_eq!(
+            new_york("FREQ=DAILY;COUNT=3", "2024-11-02 01:30"),
+            [
+                "2024-11-02 01:30 -0400",
+                "2024-11-03 01:30 -0400",
+                "2024-11-04 01:30 -0500"
+            ]
+        );
+    }
+
+    #[test]
+    fn hourly_rules_do_not_repeat_shifted_instants() {
+        assert_eq!(
+            new_york("FREQ=HOURLY;COUNT=3", "2024-03-10 01:00"),
+            [
+                "2024-03-10 01:00 -0500",
+                "2024-03-10 03:00 -0400",
+                "2024-03-10 04:00 -0400"
+            ]
+        );
+    }
+
+    fn round_trip(text: &str) -> String {
+        text.parse::<RecurrenceRule>().unwrap().to_string()
+    }
+
+    #[test]
+    fn rules_round_trip() {
+        for text in [
+            "FREQ=DAILY",
+            "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE,FR;WKST=SU;COUNT=10",
+            "FREQ=MONTHLY;BYDAY=-1FR;COUNT=3",
+            "FREQ=MONTHLY;BYMONTHDAY=1,15,-1;BYHOUR=9,17;BYMINUTE=30",
+            "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1",
+            "FREQ=YEARLY;BYMONTH=3;BYDAY=2SU;BYHOUR=2",
+            "FREQ=YEARLY;BYWEEKNO=20;BYDAY=MO",
+            "FREQ=YEARLY;BYYEARDAY=1,100,-1;UNTIL=20301231T235959Z",
+            "FREQ=HOURLY;INTERVAL=6;BYSECOND=0,30",
+        ] {
+            assert_eq!(round_trip(text), text);
+        }
+    }
+
+    #[test]
+    fn until_keeps_its_value_type() {
+        assert_eq!(
+            round_trip("FREQ=DAILY;UNTIL=20241231"),
+            "FREQ=DAILY;UNTIL=20241231"
+        );
+        assert_eq!(
+            round_trip("FREQ=DAILY;UNTIL=20241231T000000Z"),
+            "FREQ=DAILY;UNTIL=20241231T000000Z"
+        );
+        let rule: RecurrenceRule = "FREQ=DAILY;UNTIL=20241231".parse().unwrap();
+        assert!(rule.until_is_date);
+        assert_eq!(rule.until, Some(date("2024-12-31 00:00").datetime));
+    }
+
+    #[test]
+    fn parts_are_normalised() {
+        assert_eq!(
+            round_trip("RRULE:count=2;freq=weekly;byday=fr,mo;interval=1"),
+            "FREQ=WEEKLY;BYDAY=FR,MO;COUNT=2"
+        );
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        let parse = |text: &str| text.parse::<RecurrenceRule>();
+        assert_eq!(parse("INTERVAL=2"), Err(DateError::MissingRulePart("FREQ")));
+        assert!(matches!(
+            parse("FREQ=DAILY;FOO=1"),
+            Err(DateError::UnknownRulePart(_))
+        ));
+        assert!(matches!(
+            parse("FREQ=DAILY;COUNT=1;COUNT=2"),
+            Err(DateError::DuplicateRulePart(_))
+        ));
+        assert!(matches!(
+            parse("FREQ=DAILY;COUNT=2;UNTIL=20241231"),
+            Err(DateError::ConflictingRuleParts { .. })
+        ));
+        assert!(matches!(
+            parse("FREQ=MONTHLY;BYMONTHDAY=32"),
+            Err(DateError::InvalidRulePart { .. })
+        ));
+    }
+
+    fn text(rule: &str, locale: &str) -> String {
+        rule.parse::<RecurrenceRule>().unwrap().to_text(locale)
+    }
+
+    #[test]
+    fn describes_rules_in_english_and_spanish() {
+        let rule = "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;UNTIL=20241231T235959Z";
+        assert_eq!(
+            text(rule, "en"),
+            "every 2 weeks on Monday and Wednesday, until Dec 31, 2024"
+        );
+        assert_eq!(
+            text(rule, "es"),
+            "cada 2 semanas el lunes y miércoles, hasta el 31 dic 2024"
+        );
+        assert_eq!(
+            text("FREQ=MONTHLY;BYDAY=-1FR;COUNT=3", "en"),
+            "every month on the last Friday, 3 times"
+        );
+    }
+
+    #[test]
+    fn time_parts_are_described_without_inventing_values() {
+        assert_eq!(
+            text("FREQ=HOURLY;BYMINUTE=15", "en"),
+            "every hour at minute 15"
+        );
+        assert_eq!(
+            text("FREQ=MINUTELY;INTERVAL=15;BYHOUR=9", "en"),
+            "every 15 minutes at hour 9"
+        );
+        assert_eq!(
+            text("FREQ=DAILY;BYHOUR=17,9;BYMINUTE=30", "en"),
+            "every day at 09:30 and 17:30"
+        );
+        assert_eq!(
+            text("FREQ=MINUTELY;BYSECOND=0,30", "es"),
+            "cada minuto en el segundo 0 y 30"
+        );
+        assert_eq!(
+            text("FREQ=HOURLY;BYMINUTE=15", "es"),
+            "cada hora en el minuto 15"
+        );
+    }
+
+    #[test]
+    fn before_stops_at_until() {
+        let daily = generator("2024-03-01 09:00", "FREQ=DAILY;UNTIL=20240310T090000Z");
+        assert_eq!(
+            daily.before(&date("3000-01-01 00:00")),
+            Some(date("2024-03-10 09:00"))
+        );
+        assert_eq!(
+            daily.before(&date("2024-03-10 09:00")),
+            Some(date("2024-03-09 09:00"))
+        );
+        assert_eq!(daily.before(&date("2024-03-01 09:00")), None);
+
+        let weekly = generator("2024-03-01 09:00", "FREQ=WEEKLY;UNTIL=20240320T000000Z");
+        assert_eq!(
+            weekly.before(&date("2025-01-01 00:00")),
+            Some(date("2024-03-15 09:00"))
+        );
     }
 }
@@ -0,0 +1,427 @@
+// src/relative.rs
+use crate::locale::LocaleManager;
+use crate::manipulator::DurationUnit::{self, Days, Hours, Minutes, Months, Seconds, Weeks, Years};
+use crate::plural::{
+    self,
+    PluralCategory::{Few, Many, One, Other},
+    PluralForms,
+};
+use crate::DateX;
+use chrono::{Duration, Utc};
+use std::sync::LazyLock;
+use RelativeStyle::{Long, Narrow, Short};
+
+/// How long the unit names in a relative time are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RelativeStyle {
+    /// "in 3 months"
+    #[default]
+    Long,
+    /// "in 3 mo."
+    Short,
+    /// "in 3mo"
+    Narrow,
+}
+
+/// Whether a relative time may use words such as "yesterday" instead of a number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RelativeNumeric {
+    /// Always "1 day ago"; only a difference that rounds to zero is "now".
+    #[default]
+    Always,
+    /// "yesterday", "next week" or "now" when the locale has such a word.
+    Auto,
+}
+
+/// The limits at which a relative time moves to the next larger unit, as in
+/// moment.js. Amounts are rounded to the nearest whole unit before comparing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RelativeThresholds {
+    /// Up to this many seconds the difference is "now" in `Auto` mode.
+    pub few_seconds: i64,
+    /// Below this many seconds, seconds are used.
+    pub seconds: i64,
+    /// Below this many minutes, minutes are used.
+    pub minutes: i64,
+    /// Below this many hours, hours are used.
+    pub hours: i64,
+    /// Below this many days, days are used.
+    pub days: i64,
+    /// Below this many weeks, weeks are used; `None` skips weeks entirely.
+    pub weeks: Option<i64>,
+    /// Below this many months, months are used; beyond that, years.
+    pub months: i64,
+}
+
+impl Default for RelativeThresholds {
+    /// The moment.js defaults.
+    fn default() -> Self {
+        RelativeThresholds {
+            few_seconds: 44,
+            seconds: 45,
+            minutes: 45,
+            hours: 22,
+            days: 26,
+            weeks: None,
+            months: 11,
+        }
+    }
+}
+
+/// The locales every [`RelativeFormatter`] takes its words from.
+static LOCALES: LazyLock<LocaleManager> = LazyLock::new(LocaleManager::new);
+
+/// The words [`RelativeFormatter`] uses for one language.
+#[derive(Debug, Clone)]
+pub struct RelativeWords {
+    /// Wraps a past amount, e.g. "{} ago".
+    pub past: &'static str,
+    /// Wraps a future amount, e.g. "in {}".
+    pub future: &'static str,
+    /// The amount of each unit in each style per plural category, e.g. "{} min."
+    /// Missing styles fall back to the next longer one, and long amounts that are
+    /// not listed use [`LocaleManager::format_unit`].
+    pub units: &'static [(RelativeStyle, DurationUnit, PluralForms)],
+    /// Words used instead of an amount in `Auto` mode, e.g. -1 day is "yesterday".
+    pub named: &'static [(DurationUnit, i64, &'static str)],
+}
+
+/// The English relative-time words.
+pub const ENGLISH_RELATIVE_WORDS: RelativeWords = RelativeWords {
+    past: "{} ago",
+    future: "in {}",
+    units: &[
+        (Short, Seconds, &[(Other, "{} sec.")]),
+        (Short, Minutes, &[(Other, "{} min.")]),
+        (Short, Hours, &[(Other, "{} hr.")]),
+        (Short, Days, &[(One, "{} day"), (Other, "{} days")]),
+        (Short, Weeks, &[(Other, "{} wk.")]),
+        (Short, Months, &[(Other, "{} mo.")]),
+        (Short, Years, &[(Other, "{} yr.")]),
+        (Narrow, Seconds, &[(Other, "{}s")]),
+        (Narrow, Minutes, &[(Other, "{}m")]),
+        (Narrow, Hours, &[(Other, "{}h")]),
+        (Narrow, Days, &[(Other, "{}d")]),
+        (Narrow, Weeks, &[(Other, "{}w")]),
+        (Narrow, Months, &[(Other, "{}mo")]),
+        (Narrow, Years, &[(Other, "{}y")]),
+    ],
+    named: &[
+        (Seconds, 0, "now"),
+        (Days, -1, "yesterday"),
+        (Days, 1, "tomorrow"),
+        (Weeks, -1, "last week"),
+        (Weeks, 1, "next week"),
+        (Months, -1, "last month"),
+        (Months, 1, "next month"),
+        (Years, -1, "last year"),
+        (Years, 1, "next year"),
+    ],
+};
+
+/// The Spanish relative-time words.
+pub const SPANISH_RELATIVE_WORDS: RelativeWords = RelativeWords {
+    past: "hace {}",
+    future: "dentro de {}",
+    units: &[
+        (Short, Seconds, &[(Other, "{} s")]),
+        (Short, Minutes, &[(Other, "{} min")]),
+        (Short, Hours, &[(Other, "{} h")]),
+        (Short, Days, &[(Other, "{} d")]),
+        (Short, Weeks, &[(Other, "{} sem.")]),
+        (Short, Months, &[(Other, "{} m")]),
+        (Short, Years, &[(Other, "{} a")]),
+    ],
+    named: &[
+        (Seconds, 0, "ahora"),
+        (Days, -2, "anteayer"),
+        (Days, -1, "ayer"),
+        (Days, 1, "mañana"),
+        (Days, 2, "pasado mañana"),
+        (Weeks, -1, "la semana pasada"),
+        (Weeks, 1, "la próxima semana"),
+        (Months, -1, "el mes pasado"),
+        (Months, 1, "el próximo mes"),
+        (Years, -1, "el año pasado"),
+        (Years, 1, "el próximo año"),
+    ],
+};
+
+/// The Polish relative-time words. Seconds, minutes and hours are listed in the
+/// accusative, so "1 minutę temu" differs from the duration "1 minuta".
+pub const POLISH_RELATIVE_WORDS: RelativeWords = RelativeWords {
+    past: "{} temu",
+    future: "za {}",
+    units: &[
+        (
+            Long,
+            Seconds,
+            &[
+                (One, "{} sekundę"),
+                (Few, "{} sekundy"),
+                (Many, "{} sekund"),
+                (Other, "{} sekundy"),
+            ],
+        ),
+        (
+            Long,
+            Minutes,
+            &[
+                (One, "{} minutę"),
+                (Few, "{} minuty"),
+                (Many, "{} minut"),
+                (Other, "{} minuty"),
+            ],
+        ),
+        (
+            Long,
+            Hours,
+            &[
+                (One, "{} godzinę"),
+                (Few, "{} godziny"),
+                (Many, "{} godzin"),
+                (Other, "{} godziny"),
+            ],
+        ),
+        (Short, Seconds, &[(Other, "{} sek.")]),
+        (Short, Minutes, &[(Other, "{} min")]),
+        (Short, Hours, &[(Other, "{} godz.")]),
+        (Short, Weeks, &[(Other, "{} tydz.")]),
+        (Short, Months, &[(Other, "{} mies.")]),
+    ],
+    named: &[
+        (Seconds, 0, "teraz"),
+        (Days, -2, "przedwczoraj"),
+        (Days, -1, "wczoraj"),
+        (Days, 1, "jutro"),
+        (Days, 2, "pojutrze"),
+        (Weeks, -1, "w zeszłym tygodniu"),
+        (Weeks, 1, "w przyszłym tygodniu"),
+        (Months, -1, "w zeszłym miesiącu"),
+        (Months, 1, "w przyszłym miesiącu"),
+        (Years, -1, "w zeszłym roku"),
+        (Years, 1, "w przyszłym roku"),
+    ],
+};
+
+/// Describes how far a date is from a reference instant, e.g. "3 hours ago",
+/// "in 2 days" or "yesterday".
+#[derive(Debug, Clone)]
+pub struct RelativeFormatter {
+    /// The instant differences are measured from; `None` means now.
+    pub reference: Option<DateX>,
+    pub style: RelativeStyle,
+    pub numeric: RelativeNumeric,
+    pub thresholds: RelativeThresholds,
+    pub locale_code: String,
+}
+
+impl Default for RelativeFormatter {
+    fn default() -> Self {
+        RelativeFormatter {
+            reference: None,
+            style: RelativeStyle::default(),
+            numeric: RelativeNumeric::default(),
+            thresholds: RelativeThresholds::default(),
+            locale_code: "en".to_string(),
+        }
+    }
+}
+
+impl RelativeFormatter {
+    /// Creates an English formatter measuring from now with the moment.js thresholds.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a formatter for the given locale, falling back to English words.
+    pub fn for_locale(locale_code: &str) -> Self {
+        RelativeFormatter {
+            locale_code: locale_code.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Describes `date` relative to the reference instant.
+    pub fn format(&self, date: &DateX) -> String {
+        let reference = self
+            .reference
+            .as_ref()
+            .map_or_else(Utc::now, |r| r.datetime);
+        self.format_duration(date.datetime - reference)
+    }
+
+    /// Describes a signed difference: negative is in the past, otherwise the future.
+    pub fn format_duration(&self, difference: Duration) -> String {
+        let locale = &*LOCALES;
+        let words = locale.relative_words(&self.locale_code);
+        let past = difference < Duration::zero();
+        let (amount, unit) = self.select(difference);
+
+        let few_seconds = unit == DurationUnit::Seconds
+            && (amount == 0
+                || (self.numeric == RelativeNumeric::Auto
+                    && amount <= self.thresholds.few_seconds));
+        let key = match (few_seconds, self.numeric) {
+            (true, _) => Some(0),
+            (false, RelativeNumeric::Auto) => Some(if past { -amount } else { amount }),
+            (false, RelativeNumeric::Always) => None,
+        };
+        if let Some(key) = key {
+            if let Some((_, _, name)) = words.named.iter().find(|(u, v, _)| *u == unit && *v == key)
+            {
+                return name.to_string();
+            }
+        }
+
+        // A locale without narrow or short names uses the next longer style.
+        let styles: &[RelativeStyle] = match self.style {
+            Narrow => &[Narrow, Short, Long],
+            Short => &[Short, Long],
+            Long => &[Long],
+        };
+        let text = match styles.iter().find_map(|wanted| {
+            words
+                .units
+                .iter()
+                .find(|(style, u, _)| style == wanted && *u == unit)
+        }) {
+            Some((_, _, forms)) => {
+                let category = locale.plural_category(&self.locale_code, amount);
+                plural::select(forms, category).replace("{}", &amount.to_string())
+            }
+            None => locale.format_unit(&self.locale_code, amount, unit),
+        };
+        let wrapper = if past { words.past } else { words.future };
+        wrapper.replace("{}", &text)
+    }
+
+    /// Picks the unit and the rounded, unsigned amount for a difference, moving to
+    /// a larger unit as each threshold is reached.
+    fn select(&self, difference: Duration) -> (i64, DurationUnit) {
+        let limits = &self.thresholds;
+        let seconds_exact = difference.num_milliseconds().unsigned_abs() as f64 / 1000.0;
+        let round = |value: f64| value.round() as i64;
+
+        let seconds = round(seconds_exact);
+        let minutes = round(seconds_exact / 60.0);
+        let hours = round(seconds_exact / 3600.0);
+        let days_exact = seconds_exact / 86400.0;
+        let days = round(days_exact);
+        let weeks = round(days_exact / 7.0);
+        // Average Gregorian month length, as moment.js uses.
+        let months_exact = days_exact * 4800.0 / 146097.0;
+        let months = round(months_exact);
+        let years = round(months_exact / 12.0);
+
+        if seconds < limits.seconds {
+            (seconds, DurationUnit::Seconds)
+        } else if minutes <= 1 {
+            (1, DurationUnit::Minutes)
+        } else if minutes < limits.minutes {
+            (minutes, DurationUnit::Minutes)
+        } else if hours <= 1 {
+            (1, DurationUnit::Hours)
+        } else if hours < limits.hours {
+            (hours, DurationUnit::Hours)
+        } else if days <= 1 {
+            (1, DurationUnit::Days)
+        } else if days < limits.days {
+            (days, DurationUnit::Days)
+        } else if limits.weeks.is_some_and(|limit| weeks < limit) {
+            (weeks.max(1), DurationUnit::Weeks)
+        } else if months <= 1 {
+            (1, DurationUnit::Months)
+        } else if months < limits.months {
+            (months, DurationUnit::Months)
+        } else {
+            (years.max(1), DurationUnit::Years)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn relative(locale_code: &str, style: RelativeStyle, difference: Duration) -> String {
+        RelativeFormatter {
+            style,
+            ..RelativeFormatter::for_locale(locale_code)
+        }
+        .format_duration(difference)
+    }
+
+    fn auto(locale_code: &str, difference: Duration) -> String {
+        RelativeFormatter {
+            numeric: RelativeNumeric::Auto,
+            ..RelativeFormatter::for_locale(locale_code)
+        }
+        .format_duration(difference)
+    }
+
+    #[test]
+    fn moment_thresholds() {
+        let ago = |seconds: i64| relative("en", Long, Duration::seconds(-seconds));
+        assert_eq!(ago(44), "44 seconds ago");
+        assert_eq!(ago(45), "1 minute ago");
+        assert_eq!(ago(89), "1 minute ago");
+        assert_eq!(ago(90), "2 minutes ago");
+        assert_eq!(ago(25 * 86400), "25 days ago");
+        assert_eq!(ago(26 * 86400), "1 month ago");
+        assert_eq!(ago(-25 * 86400), "in 25 days");
+    }
+
+    #[test]
+    fn zero_is_now() {
+        assert_eq!(relative("en", Long, Duration::zero()), "now");
+        assert_eq!(relative("en", Long, Duration::milliseconds(-400)), "now");
+        assert_eq!(relative("es", Short, Duration::zero()), "ahora");
+        assert_eq!(auto("en", Duration::zero()), "now");
+        assert_eq!(relative("en", Long, Duration::seconds(1)), "in 1 second");
+    }
+
+    #[test]
+    fn auto_uses_named_words() {
+        assert_eq!(auto("en", Duration::seconds(-44)), "now");
+        assert_eq!(auto("en", Duration::seconds(-45)), "1 minute ago");
+        assert_eq!(auto("en", Duration::days(-1)), "yesterday");
+        assert_eq!(auto("en", Duration::hours(22)), "tomorrow");
+        assert_eq!(auto("en", Duration::days(2)), "in 2 days");
+        assert_eq!(auto("en", Duration::days(-26)), "last month");
+        assert_eq!(auto("es", Duration::days(-2)), "anteayer");
+        assert_eq!(auto("es", Duration::days(1)), "mañana");
+        assert_eq!(auto("pl", Duration::days(-1)), "wczoraj");
+    }
+
+    #[test]
+    fn long_style_uses_locale_unit_names() {
+        assert_eq!(relative("en", Long, Duration::hours(-3)), "3 hours ago");
+        assert_eq!(relative("en", Long, Duration::days(1)), "in 1 day");
+        assert_eq!(relative("es", Long, Duration::days(-3)), "hace 3 días");
+        assert_eq!(relative("pl", Long, Duration::days(5)), "za 5 dni");
+        assert_eq!(relative("fr", Long, Duration::minutes(10)), "in 10 minutes");
+        let million_years = Duration::days(365_242_500);
+        assert_eq!(
+            relative("es", Long, million_years),
+            "dentro de 1000000 de años"
+        );
+    }
+
+    #[test]
+    fn polish_uses_the_accusative() {
+        assert_eq!(relative("pl", Long, Duration::minutes(-1)), "1 minutę temu");
+        assert_eq!(relative("pl", Long, Duration::minutes(-2)), "2 minuty temu");
+        assert_eq!(relative("pl", Long, Duration::minutes(-5)), "5 minut temu");
+        assert_eq!(relative("pl", Long, Duration::hours(21)), "za 21 godzin");
+    }
+
+    #[test]
+    fn short_and_narrow_fall_back_to_longer_styles() {
+        assert_eq!(relative("en", Short, Duration::minutes(-5)), "5 min. ago");
+        assert_eq!(relative("en", Narrow, Duration::minutes(-5)), "5m ago");
+        assert_eq!(relative("es", Narrow, Duration::hours(3)), "dentro de 3 h");
+        assert_eq!(relative("pl", Short, Duration::days(-2)), "2 dni temu");
+        assert_eq!(relative("pl", Narrow, Duration::hours(3)), "za 3 godz.");
+    }
+}
@@ -1,8 +1,27 @@
 // src/timezone.rs
+use crate::error::DateError;
 use chrono::Offset;
-use chrono::{DateTime, TimeZone, Utc};
+use chrono::{DateTime, Duration, LocalResult, NaiveDateTime, TimeZone, Utc};
 use chrono_tz::Tz;
 use std::str::FromStr;
+
+/// How to resolve a local wall-clock time that a DST transition skips (gap)
+/// or repeats (overlap).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalTimePolicy {
+    /// Pick the earlier of the two candidate instants. In a gap this is the
+    /// local time interpreted with the offset after the transition.
+    Earliest,
+    /// Pick the later of the two candidate instants. In a gap this is the
+    /// local time interpreted with the offset before the transition.
+    Latest,
+    /// Fail with `NonexistentLocalTime` or `AmbiguousLocalTime`.
+    Reject,
+    /// Push times in a gap forward by the length of the gap and resolve
+    /// overlaps to the earlier instant, as RFC 5545 prescribes.
+    ShiftForward,
+}
+
 /// Provides functionalities for time zone conversions.
 pub struct TimeZoneHandler;
 
@@ -11,10 +30,10 @@ impl TimeZoneHandler {
     pub fn convert_to_timezone(
         datetime: DateTime<Utc>,
         timezone: &str,
-    ) -> Result<DateTime<Tz>, String> {
+    ) -> Result<DateTime<Tz>, DateError> {
         match Tz::from_str(timezone) {
             Ok(tz) => Ok(datetime.with_timezone(&tz)),
-            Err(_) => Err("Invalid timezone".to_string()),
+            Err(_) => Err(DateError::InvalidTimezone(timezone.to_string())),
         }
     }
 
@@ -22,13 +41,66 @@ impl TimeZoneHandler {
     pub fn get_timezone_offset(
         datetime: DateTime<Utc>,
         timezone: &str,
-    ) -> Result<chrono::FixedOffset, String> {
+    ) -> Result<chrono::FixedOffset, DateError> {
         match Tz::from_str(timezone) {
             Ok(tz) => {
                 let offset = tz.offset_from_utc_datetime(&datetime.naive_utc());
                 Ok(offset.fix())
             }
-            Err(_) => Err("Invalid timezone".to_string()),
+            Err(_) => Err(DateError::InvalidTimezone(timezone.to_string())),
+        }
+    }
+
+    /// Resolves a local wall-clock time in a named time zone using the given policy.
+    pub fn from_local(
+        naive: NaiveDateTime,
+        timezone: &str,
+        policy: LocalTimePolicy,
+    ) -> Result<DateTime<Tz>, DateError> {
+        match Tz::from_str(timezone) {
+            Ok(tz) => Self::resolve_local(tz, naive, policy),
+            Err(_) => Err(DateError::InvalidTimezone(timezone.to_string())),
+        }
+    }
+
+    /// Resolves a local wall-clock time in the given time zone using the given policy.
+    pub fn resolve_local(
+        tz: Tz,
+        naive: NaiveDateTime,
+        policy: LocalTimePolicy,
+    ) -> Result<DateTime<Tz>, DateError> {
+        match tz.from_local_datetime(&naive) {
+            LocalResult::Single(datetime) => Ok(datetime),
+            LocalResult::Ambiguous(earliest, latest) => match policy {
+                LocalTimePolicy::Earliest | LocalTimePolicy::ShiftForward => Ok(earliest),
+                LocalTimePolicy::Latest => Ok(latest),
+                LocalTimePolicy::Reject => Err(DateError::AmbiguousLocalTime {
+                    local: naive,
+                    timezone: tz.name().to_string(),
+                }),
+            },
+            LocalResult::None => {
+                // chrono-tz does not expose its transition table, so the offsets in
+                // effect a day either side stand in for those just before and after
+                // the gap. Known limitation: this is wrong if another transition lies
+                // within a day of the gap.
+                let before = tz.offset_from_utc_datetime(&(naive - Duration::days(1)));
+                let after = tz.offset_from_utc_datetime(&(naive + Duration::days(1)));
+                let with_offset = |offset: <Tz as TimeZone>::Offset| {
+                    let utc = naive - Duration::seconds(offset.fix().local_minus_utc() as i64);
+                    tz.from_utc_datetime(&utc)
+                };
+                match policy {
+                    LocalTimePolicy::Earliest => Ok(with_offset(after)),
+                    LocalTimePolicy::Latest | LocalTimePolicy::ShiftForward => {
+                        Ok(with_offset(before))
+                    }
+                    LocalTimePolicy::Reject => Err(DateError::NonexistentLocalTime {
+                        local: naive,
+                        timezone: tz.name().to_string(),
+                    }),
+                }
+            }
         }
     }
 
@@ -37,3 +109,72 @@ impl TimeZoneHandler {
         chrono_tz::TZ_VARIANTS.iter().map(|tz| tz.name()).collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(local: &str, policy: LocalTimePolicy) -> Result<String, DateError> {
+        let naive = NaiveDateTime::parse_from_str(local, "%Y-%m-%d %H:%M").unwrap();
+        TimeZoneHandler::resolve_local(chrono_tz::America::New_York, naive, policy)
+            .map(|datetime| datetime.format("%Y-%m-%d %H:%M %z").to_string())
+    }
+
+    const GAP: &str = "2024-03-10 02:30";
+    const OVERLAP: &str = "2024-11-03 01:30";
+
+    #[test]
+    fn gap_for_each_policy() {
+        assert_eq!(
+            resolve(GAP, LocalTimePolicy::Earliest).unwrap(),
+            "2024-03-10 01:30 -0500"
+        );
+        assert_eq!(
+            resolve(GAP, LocalTimePolicy::Latest).unwrap(),
+            "2024-03-10 03:30 -0400"
+        );
+        assert_eq!(
+            resolve(GAP, LocalTimePolicy::ShiftForward).unwrap(),
+            "2024-03-10 03:30 -0400"
+        );
+        assert!(matches!(
+            resolve(GAP, LocalTimePolicy::Reject),
+            Err(DateError::NonexistentLocalTime { .. })
+        ));
+    }
+
+    #[test]
+    fn overlap_for_each_policy() {
+        assert_eq!(
+            resolve(OVERLAP, LocalTimePolicy::Earliest).unwrap(),
+            "2024-11-03 01:30 -0400"
+        );
+        assert_eq!(
+            resolve(OVERLAP, LocalTimePolicy::Latest).unwrap(),
+            "2024-11-03 01:30 -0500"
+        );
+        assert_eq!(
+            resolve(OVERLAP, LocalTimePolicy::ShiftForward).unwrap(),
+            "2024-11-03 01:30 -0400"
+        );
+        assert!(matches!(
+            resolve(OVERLAP, LocalTimePolicy::Reject),
+            Err(DateError::AmbiguousLocalTime { .. })
+        ));
+    }
+
+    #[test]
+    fn unambiguous_times_ignore_the_policy() {
+        for policy in [
+            LocalTimePolicy::Earliest,
+            LocalTimePolicy::Latest,
+            LocalTimePolicy::Reject,
+            LocalTimePolicy::ShiftForward,
+        ] {
+            assert_eq!(
+                resolve("2024-07-01 12:00", policy).unwrap(),
+                "2024-07-01 12:00 -0400"
+            );
+        }
+    }
+}
@@ -16,11 +16,16 @@ pub fn get_week_of_year(date: NaiveDate) -> u32 {
 
 /// Returns the ordinal suffix for a given day.
 pub fn get_ordinal_suffix(day: u32) -> String {
+    ordinal_suffix(day).to_string()
+}
+
+/// Returns the ordinal suffix for a given day without allocating.
+pub fn ordinal_suffix(day: u32) -> &'static str {
     match day {
-        1 | 21 | 31 => "st".to_string(),
-        2 | 22 => "nd".to_string(),
-        3 | 23 => "rd".to_string(),
-        _ => "th".to_string(),
+        1 | 21 | 31 => "st",
+        2 | 22 => "nd",
+        3 | 23 => "rd",
+        _ => "th",
     }
 }
 
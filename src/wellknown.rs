@@ -0,0 +1,560 @@
+// src/wellknown.rs
+use crate::error::DateError;
+use crate::formatter::{DateFormatter, MONTH_NAMES, WEEKDAY_NAMES};
+use crate::parsed::{abbreviation_offset, parse_offset};
+use crate::parser::{out_of_range, DateParser};
+use crate::DateX;
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc,
+    Weekday,
+};
+use regex::{Captures, Regex};
+use std::fmt::{self, Write};
+use std::sync::LazyLock;
+
+/// A format accepted by [`DateX::format`](crate::DateX::format) and
+/// [`DateX::parse`](crate::DateX::parse): a strftime-style string or a
+/// [`WellKnownFormat`].
+pub trait DateFormat {
+    /// Formats a date in its own time zone.
+    fn format_datetime<Tz: TimeZone>(&self, datetime: &DateTime<Tz>) -> String
+    where
+        Tz::Offset: fmt::Display;
+
+    /// Parses the input into a UTC instant.
+    fn parse_datetime(&self, input: &str) -> Result<DateTime<Utc>, DateError>;
+}
+
+/// Any string is a strftime-style format.
+impl<S: AsRef<str>> DateFormat for S {
+    fn format_datetime<Tz: TimeZone>(&self, datetime: &DateTime<Tz>) -> String
+    where
+        Tz::Offset: fmt::Display,
+    {
+        DateFormatter::format_datetime(datetime, self.as_ref())
+    }
+
+    fn parse_datetime(&self, input: &str) -> Result<DateTime<Utc>, DateError> {
+        DateParser::parse(input, self.as_ref())
+    }
+}
+
+/// Standard date formats used by protocols, file formats and databases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WellKnownFormat {
+    /// ISO 8601 basic format, e.g. 20240315T103000Z. Parsing also accepts week
+    /// dates (2024W115) and ordinal dates (2024075).
+    Iso8601Basic,
+    /// ISO 8601 extended format, e.g. 2024-03-15T10:30:00Z. Parsing also accepts
+    /// week dates (2024-W11-5) and ordinal dates (2024-075).
+    Iso8601Extended,
+    /// RFC 3339 with the given number of fractional second digits (at most 9),
+    /// e.g. 2024-03-15T10:30:00.250Z. Parsing accepts any number of digits.
+    Rfc3339 { fraction_digits: u8 },
+    /// RFC 2822 Internet Message Format, e.g. Fri, 15 Mar 2024 10:30:00 +0000.
+    Rfc2822,
+    /// RFC 7231 IMF-fixdate, e.g. Fri, 15 Mar 2024 10:30:00 GMT. Parsing also
+    /// accepts the obsolete RFC 850 and asctime forms, as HTTP requires.
+    HttpDate,
+    /// RFC 850 date, e.g. Friday, 15-Mar-24 10:30:00 GMT.
+    Rfc850,
+    /// C `asctime()` date, e.g. Fri Mar 15 10:30:00 2024.
+    Asctime,
+    /// Netscape cookie expiry date, e.g. Fri, 15-Mar-2024 10:30:00 GMT. Parsing
+    /// follows the lenient algorithm of RFC 6265.
+    Cookie,
+    /// SQL datetime, e.g. 2024-03-15 10:30:00.
+    Sql,
+}
+
+impl fmt::Display for WellKnownFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            WellKnownFormat::Iso8601Basic => "ISO 8601 basic",
+            WellKnownFormat::Iso8601Extended => "ISO 8601 extended",
+            WellKnownFormat::Rfc3339 { .. } => "RFC 3339",
+            WellKnownFormat::Rfc2822 => "RFC 2822",
+            WellKnownFormat::HttpDate => "HTTP-date",
+            WellKnownFormat::Rfc850 => "RFC 850",
+            WellKnownFormat::Asctime => "asctime",
+            WellKnownFormat::Cookie => "cookie",
+            WellKnownFormat::Sql => "SQL datetime",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl DateFormat for WellKnownFormat {
+    /// Formats a date; HTTP, RFC 850, asctime and cookie dates are always in GMT.
+    fn format_datetime<Tz: TimeZone>(&self, datetime: &DateTime<Tz>) -> String
+    where
+        Tz::Offset: fmt::Display,
+    {
+        let utc = datetime.with_timezone(&Utc);
+        let offset = datetime.offset().fix().local_minus_utc();
+        match *self {
+            WellKnownFormat::Iso8601Basic => {
+                let mut result = DateFormatter::format_datetime(datetime, "%Y%m%dT%H%M%S");
+                push_offset(&mut result, offset, false);
+                result
+            }
+            WellKnownFormat::Iso8601Extended => {
+                let mut result = DateFormatter::format_datetime(datetime, "%Y-%m-%dT%H:%M:%S");
+                push_offset(&mut result, offset, true);
+                result
+            }
+            WellKnownFormat::Rfc3339 { fraction_digits } => {
+                let mut result = DateFormatter::format_datetime(datetime, "%Y-%m-%dT%H:%M:%S");
+                let digits = fraction_digits.min(9) as usize;
+                if digits > 0 {
+                    let fraction = datetime.timestamp_subsec_nanos() / 10u32.pow(9 - digits as u32);
+                    let _ = write!(result, ".{:0width$}", fraction, width = digits);
+                }
+                push_offset(&mut result, offset, true);
+                result
+            }
+            WellKnownFormat::Rfc2822 => {
+                DateFormatter::format_datetime(datetime, "%a, %d %b %Y %H:%M:%S %z")
+            }
+            WellKnownFormat::HttpDate => {
+                DateFormatter::format_datetime(&utc, "%a, %d %b %Y %H:%M:%S GMT")
+            }
+            WellKnownFormat::Rfc850 => {
+                DateFormatter::format_datetime(&utc, "%A, %d-%b-%y %H:%M:%S GMT")
+            }
+            WellKnownFormat::Asctime => {
+                DateFormatter::format_datetime(&utc, "%a %b %_d %H:%M:%S %Y")
+            }
+            WellKnownFormat::Cookie => {
+                DateFormatter::format_datetime(&utc, "%a, %d-%b-%Y %H:%M:%S GMT")
+            }
+            WellKnownFormat::Sql => DateFormatter::format_datetime(datetime, "%Y-%m-%d %H:%M:%S"),
+        }
+    }
+
+    fn parse_datetime(&self, input: &str) -> Result<DateTime<Utc>, DateError> {
+        let input = input.trim();
+        let result = match self {
+            WellKnownFormat::Iso8601Basic => parse_iso8601(input, false),
+            WellKnownFormat::Iso8601Extended => parse_iso8601(input, true),
+            WellKnownFormat::Rfc3339 { .. } => DateTime::parse_from_rfc3339(input)
+                .ok()
+                .map(|datetime| Ok(datetime.with_timezone(&Utc))),
+            WellKnownFormat::Rfc2822 => DateTime::parse_from_rfc2822(&strip_comments(input))
+                .ok()
+                .map(|datetime| Ok(datetime.with_timezone(&Utc))),
+            WellKnownFormat::HttpDate => parse_imf_fixdate(input)
+                .or_else(|| parse_rfc850(input))
+                .or_else(|| parse_asctime(input)),
+            WellKnownFormat::Rfc850 => parse_rfc850(input),
+            WellKnownFormat::Asctime => parse_asctime(input),
+            WellKnownFormat::Cookie => parse_cookie_date(input),
+            WellKnownFormat::Sql => parse_sql(input),
+        };
+        result.unwrap_or_else(|| {
+            Err(DateError::WellKnownMismatch {
+                format: *self,
+                input: input.to_string(),
+            })
+        })
+    }
+}
+
+/// The outcome of reading one shape: `None` when the input does not have it.
+type Reading = Option<Result<DateTime<Utc>, DateError>>;
+
+/// Extended ISO 8601 calendar, week and ordinal dates.
+static ISO8601_EXTENDED: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?:(?P<Y>\d{4})-(?P<m>\d{2})-(?P<d>\d{2})|(?P<G>\d{4})-W(?P<V>\d{2})(?:-(?P<u>\d))?|(?P<O>\d{4})-(?P<j>\d{3}))(?:[Tt](?P<H>\d{2})(?::(?P<M>\d{2})(?::(?P<S>\d{2})(?:[.,](?P<f>\d{1,9}))?)?)?(?P<z>[Zz]|[+-]\d{2}(?::\d{2})?)?)?$",
+    )
+    .unwrap()
+});
+
+/// Basic ISO 8601 calendar, week and ordinal dates.
+static ISO8601_BASIC: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?:(?P<Y>\d{4})(?P<m>\d{2})(?P<d>\d{2})|(?P<G>\d{4})W(?P<V>\d{2})(?P<u>\d)?|(?P<O>\d{4})(?P<j>\d{3}))(?:[Tt](?P<H>\d{2})(?:(?P<M>\d{2})(?:(?P<S>\d{2})(?:[.,](?P<f>\d{1,9}))?)?)?(?P<z>[Zz]|[+-]\d{2}(?:\d{2})?)?)?$",
+    )
+    .unwrap()
+});
+
+/// RFC 7231 IMF-fixdate.
+static IMF_FIXDATE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?P<a>[A-Za-z]{3}), (?P<d>\d{2}) (?P<b>[A-Za-z]{3}) (?P<Y>\d{4}) (?P<H>\d{2}):(?P<M>\d{2}):(?P<S>\d{2}) GMT$",
+    )
+    .unwrap()
+});
+
+/// RFC 850 dates and their obsolete forms.
+static RFC850: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?P<a>[A-Za-z]+),\s*(?P<d>\d{1,2})-(?P<b>[A-Za-z]{3})-(?P<Y>\d{4}|\d{2})\s+(?P<H>\d{2}):(?P<M>\d{2}):(?P<S>\d{2})\s+(?P<z>[A-Za-z]+|[+-]\d{4})$",
+    )
+    .unwrap()
+});
+
+/// C asctime() dates.
+static ASCTIME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?P<a>[A-Za-z]{3})\s+(?P<b>[A-Za-z]{3})\s+(?P<d>\d{1,2})\s+(?P<H>\d{2}):(?P<M>\d{2}):(?P<S>\d{2})\s+(?P<Y>\d{4})$",
+    )
+    .unwrap()
+});
+
+/// SQL datetimes.
+static SQL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?P<Y>\d{4})-(?P<m>\d{2})-(?P<d>\d{2})[ T](?P<H>\d{2}):(?P<M>\d{2}):(?P<S>\d{2})(?:\.(?P<f>\d{1,9}))?$",
+    )
+    .unwrap()
+});
+
+/// A cookie-date time token.
+static COOKIE_TIME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d{1,2}):(\d{1,2}):(\d{1,2})(?:\D.*)?$").unwrap());
+
+/// A cookie-date day-of-month token.
+static COOKIE_DAY: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(\d{1,2})(?:\D.*)?$").unwrap());
+
+/// A cookie-date year token.
+static COOKIE_YEAR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d{2,4})(?:\D.*)?$").unwrap());
+
+/// Appends "Z" for UTC, otherwise "+HH:MM" (extended) or "+HHMM" (basic).
+fn push_offset(result: &mut String, offset: i32, extended: bool) {
+    if offset == 0 {
+        result.push('Z');
+        return;
+    }
+    let sign = if offset < 0 { '-' } else { '+' };
+    let minutes = (offset.abs() + 30) / 60;
+    let separator = if extended { ":" } else { "" };
+    let _ = write!(
+        result,
+        "{}{:02}{}{:02}",
+        sign,
+        minutes / 60,
+        separator,
+        minutes % 60
+    );
+}
+
+/// ISO 8601 calendar (2024-03-15), week (2024-W11-5) and ordinal (2024-075)
+/// dates with an optional time, fraction and offset, in basic (20240315T103000Z)
+/// or extended (2024-03-15T10:30:00Z) form.
+fn parse_iso8601(input: &str, extended: bool) -> Reading {
+    let re = if extended {
+        &ISO8601_EXTENDED
+    } else {
+        &ISO8601_BASIC
+    };
+    let caps = re.captures(input)?;
+    let offset = match caps.name("z") {
+        Some(zone) => match parse_offset(&zone.as_str().to_ascii_uppercase()) {
+            Ok(offset) => offset,
+            Err(e) => return Some(Err(e)),
+        },
+        None => 0,
+    };
+    let date = if caps.name("V").is_some() {
+        let weekday = caps.name("u").map_or(1, |_| number(&caps, "u"));
+        DateX::from_iso_week_date(number(&caps, "G") as i32, number(&caps, "V"), weekday)
+            .map(|datex| datex.datetime.date_naive())
+    } else if caps.name("j").is_some() {
+        DateX::from_ordinal(number(&caps, "O") as i32, number(&caps, "j"))
+            .map(|datex| datex.datetime.date_naive())
+    } else {
+        DateParser::build_date(
+            number(&caps, "Y") as i32,
+            number(&caps, "m"),
+            number(&caps, "d"),
+        )
+    };
+    Some(date.and_then(|date| instant_on(&caps, date, offset)))
+}
+
+/// RFC 7231 IMF-fixdate: Fri, 15 Mar 2024 10:30:00 GMT.
+fn parse_imf_fixdate(input: &str) -> Reading {
+    let caps = IMF_FIXDATE.captures(input)?;
+    let month = month_number(&caps["b"])?;
+    let weekday = weekday_named(&caps["a"])?;
+    Some(
+        instant(&caps, number(&caps, "Y") as i32, month, 0)
+            .and_then(|i| check_weekday(i, 0, weekday)),
+    )
+}
+
+/// RFC 850: Friday, 15-Mar-24 10:30:00 GMT. The obsolete forms with an
+/// abbreviated weekday, a four-digit year or another zone are also accepted.
+fn parse_rfc850(input: &str) -> Reading {
+    let caps = RFC850.captures(input)?;
+    let month = month_number(&caps["b"])?;
+    let weekday = weekday_named(&caps["a"])?;
+    let year = match caps["Y"].len() {
+        2 => expand_two_digit_year(number(&caps, "Y") as i32),
+        _ => number(&caps, "Y") as i32,
+    };
+    let zone = &caps["z"];
+    let offset = if zone.starts_with(['+', '-']) {
+        parse_offset(zone)
+    } else {
+        abbreviation_offset(zone).ok_or_else(|| DateError::InvalidTimezone(zone.to_string()))
+    };
+    Some(offset.and_then(|offset| {
+        instant(&caps, year, month, offset).and_then(|i| check_weekday(i, offset, weekday))
+    }))
+}
+
+/// C asctime(): Fri Mar 15 10:30:00 2024, with the day padded by a space.
+fn parse_asctime(input: &str) -> Reading {
+    let caps = ASCTIME.captures(input)?;
+    let month = month_number(&caps["b"])?;
+    let weekday = weekday_named(&caps["a"])?;
+    Some(
+        instant(&caps, number(&caps, "Y") as i32, month, 0)
+            .and_then(|i| check_weekday(i, 0, weekday)),
+    )
+}
+
+/// The cookie-date algorithm of RFC 6265 section 5.1.1: the first time, day,
+/// month and year found among the tokens win, in any order. Always UTC.
+fn parse_cookie_date(input: &str) -> Reading {
+    let value = |caps: &Captures, i: usize| caps[i].parse::<u32>().unwrap_or(0);
+
+    let (mut time, mut day, mut month, mut year) = (None, None, None, None);
+    for token in input.split(is_cookie_delimiter).filter(|t| !t.is_empty()) {
+        if time.is_none() {
+            if let Some(caps) = COOKIE_TIME.captures(token) {
+                time = Some((value(&caps, 1), value(&caps, 2), value(&caps, 3)));
+                continue;
+            }
+        }
+        if day.is_none() {
+            if let Some(caps) = COOKIE_DAY.captures(token) {
+                day = Some(value(&caps, 1));
+                continue;
+            }
+        }
+        if month.is_none() {
+            if let Some(number) = token.get(..3).and_then(month_number) {
+                month = Some(number);
+                continue;
+            }
+        }
+        if year.is_none() {
+            if let Some(caps) = COOKIE_YEAR.captures(token) {
+                year = Some(value(&caps, 1));
+            }
+        }
+    }
+
+    let ((hour, minute, second), day, month, year) = (time?, day?, month?, year?);
+    let year = match year {
+        70..=99 => year + 1900,
+        0..=69 => year + 2000,
+        _ => year,
+    };
+    if !(1..=31).contains(&day) {
+        return Some(Err(out_of_range("day", day)));
+    }
+    if year < 1601 {
+        return Some(Err(out_of_range("year", year)));
+    }
+    let result = DateParser::build_date(year as i32, month, day).and_then(|date| {
+        let time = DateParser::build_time(hour, minute, second, 0)?;
+        Ok(NaiveDateTime::new(date, time).and_utc())
+    });
+    Some(result)
+}
+
+/// SQL datetime: 2024-03-15 10:30:00 with optional fractional seconds.
+fn parse_sql(input: &str) -> Reading {
+    let caps = SQL.captures(input)?;
+    Some(instant(
+        &caps,
+        number(&caps, "Y") as i32,
+        number(&caps, "m"),
+        0,
+    ))
+}
+
+/// Builds the instant from the day (d), time (H, M, S) and fraction (f) captures.
+fn instant(
+    caps: &Captures,
+    year: i32,
+    month: u32,
+    offset: i32,
+) -> Result<DateTime<Utc>, DateError> {
+    let date = DateParser::build_date(year, month, number(caps, "d"))?;
+    instant_on(caps, date, offset)
+}
+
+/// Builds the instant on `date` from the time (H, M, S) and fraction (f) captures.
+fn instant_on(caps: &Captures, date: NaiveDate, offset: i32) -> Result<DateTime<Utc>, DateError> {
+    let nanos = caps.name("f").map_or(0, |m| {
+        let digits = m.as_str();
+        digits.parse::<u32>().unwrap_or(0) * 10u32.pow(9 - digits.len() as u32)
+    });
+    let time = DateParser::build_time(
+        number(caps, "H"),
+        number(caps, "M"),
+        number(caps, "S"),
+        nanos,
+    )?;
+    FixedOffset::east_opt(offset)
+        .and_then(|tz| {
+            tz.from_local_datetime(&NaiveDateTime::new(date, time))
+                .single()
+        })
+        .map(|datetime| datetime.with_timezone(&Utc))
+        .ok_or(DateError::FieldOutOfRange {
+            field: "offset",
+            value: offset as i64,
+        })
+}
+
+fn number(caps: &Captures, name: &str) -> u32 {
+    caps.name(name)
+        .map_or(0, |m| m.as_str().parse().unwrap_or(0))
+}
+
+/// Reads an English month abbreviation, ignoring case.
+fn month_number(text: &str) -> Option<u32> {
+    MONTH_NAMES
+        .iter()
+        .position(|name| name[..3].eq_ignore_ascii_case(text))
+        .map(|i| i as u32 + 1)
+}
+
+/// Reads an English weekday name or abbreviation, ignoring case.
+fn weekday_named(text: &str) -> Option<Weekday> {
+    WEEKDAY_NAMES
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(text) || name[..3].eq_ignore_ascii_case(text))
+        .and_then(|i| Weekday::try_from(i as u8).ok())
+}
+
+/// Rejects a date whose stated weekday is not the weekday it falls on at the
+/// given offset.
+fn check_weekday(
+    datetime: DateTime<Utc>,
+    offset: i32,
+    weekday: Weekday,
+) -> Result<DateTime<Utc>, DateError> {
+    let date = (datetime.naive_utc() + Duration::seconds(offset as i64)).date();
+    if date.weekday() != weekday {
+        return Err(DateError::WeekdayMismatch { date, weekday });
+    }
+    Ok(datetime)
+}
+
+/// Reads a two-digit RFC 850 year as RFC 7231 requires: a year more than 50
+/// years in the future is taken to be in the previous century.
+fn expand_two_digit_year(year: i32) -> i32 {
+    let current = Utc::now().year();
+    let expanded = current - current.rem_euclid(100) + year;
+    if expanded > current + 50 {
+        expanded - 100
+    } else {
+        expanded
+    }
+}
+
+/// Replaces RFC 2822 comments such as "(EST)", which may nest, with a space.
+fn strip_comments(input: &str) -> String {
+    let mut result = String::with_capacity(input.len());
+    let mut depth = 0usize;
+    let mut escaped = false;
+    for c in input.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if depth > 0 => escaped = true,
+            '(' => {
+                if depth == 0 {
+                    result.push(' ');
+                }
+                depth += 1;
+            }
+            ')' if depth > 0 => depth -= 1,
+            _ if depth == 0 => result.push(c),
+            _ => {}
+        }
+    }
+    result.trim().to_string()
+}
+
+/// The delimiters between cookie-date tokens (RFC 6265 section 5.1.1).
+fn is_cookie_delimiter(c: char) -> bool {
+    matches!(c, '\t' | ' '..='/' | ';'..='@' | '['..='`' | '{'..='~')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(format: WellKnownFormat, input: &str) -> Result<String, DateError> {
+        format
+            .parse_datetime(input)
+            .map(|datetime| datetime.format("%Y-%m-%d %H:%M:%S").to_string())
+    }
+
+    #[test]
+    fn any_string_is_a_format() {
+        let format = String::from("%Y-%m-%d");
+        let boxed: Box<str> = "%d/%m/%Y".into();
+        let date = DateX::parse("2024-03-15", &format).unwrap();
+        assert_eq!(date.format(format.clone()), "2024-03-15");
+        assert_eq!(date.format(&boxed), "15/03/2024");
+        assert_eq!(date.format(std::borrow::Cow::Borrowed("%B")), "March");
+        assert!(DateX::parse("15/03/2024", boxed).is_ok());
+    }
+
+    #[test]
+    fn wrong_weekdays_are_reported() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        assert_eq!(
+            parse(WellKnownFormat::HttpDate, "Thu, 15 Mar 2024 10:30:00 GMT"),
+            Err(DateError::WeekdayMismatch {
+                date,
+                weekday: Weekday::Thu
+            })
+        );
+        assert_eq!(
+            parse(WellKnownFormat::Asctime, "Sat Mar 15 10:30:00 2024"),
+            Err(DateError::WeekdayMismatch {
+                date,
+                weekday: Weekday::Sat
+            })
+        );
+    }
+
+    #[test]
+    fn weekdays_are_checked_against_the_local_date() {
+        assert_eq!(
+            parse(WellKnownFormat::Rfc850, "Friday, 15-Mar-24 02:00:00 +0500").unwrap(),
+            "2024-03-14 21:00:00"
+        );
+    }
+
+    #[test]
+    fn shapes_parse_with_shared_regexes() {
+        assert_eq!(
+            parse(WellKnownFormat::Iso8601Extended, "2024-W11-5T10:30Z").unwrap(),
+            "2024-03-15 10:30:00"
+        );
+        assert_eq!(
+            parse(WellKnownFormat::Iso8601Basic, "2024075T103000+0100").unwrap(),
+            "2024-03-15 09:30:00"
+        );
+        assert_eq!(
+            parse(WellKnownFormat::Cookie, "Fri, 15-Mar-2024 10:30:00 GMT").unwrap(),
+            "2024-03-15 10:30:00"
+        );
+        assert_eq!(
+            parse(WellKnownFormat::Sql, "2024-03-15 10:30:00.5").unwrap(),
+            "2024-03-15 10:30:00"
+        );
+    }
+}
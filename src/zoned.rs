@@ -0,0 +1,326 @@
+// src/zoned.rs
+use crate::error::DateError;
+use crate::formatter::DateFormatter;
+use crate::locale::LocaleManager;
+use crate::manipulator::{DurationUnit, TimeManipulator};
+use crate::timezone::{LocalTimePolicy, TimeZoneHandler};
+use crate::utils::*;
+use crate::wellknown::DateFormat;
+use crate::DateX;
+use chrono::{DateTime, Datelike, NaiveDateTime, Offset, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+use std::fmt;
+
+/// A date and time that remembers the IANA time zone it was converted into.
+///
+/// All accessors, formatting and start/end-of-period helpers work on the local
+/// wall-clock time of that zone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZonedDateX {
+    pub datetime: DateTime<Tz>,
+}
+
+impl ZonedDateX {
+    /// Creates a new `ZonedDateX` for the current instant in the given time zone.
+    pub fn now(timezone: &str) -> Result<Self, DateError> {
+        DateX::now().to_timezone(timezone)
+    }
+
+    /// Creates a `ZonedDateX` from a local wall-clock time in the given time zone.
+    pub fn from_local(
+        naive: NaiveDateTime,
+        timezone: &str,
+        policy: LocalTimePolicy,
+    ) -> Result<Self, DateError> {
+        TimeZoneHandler::from_local(naive, timezone, policy).map(|datetime| ZonedDateX { datetime })
+    }
+
+    /// Returns the time zone of this date.
+    pub fn timezone(&self) -> Tz {
+        self.datetime.timezone()
+    }
+
+    /// Returns the IANA name of the time zone (e.g. "Europe/Berlin").
+    pub fn timezone_name(&self) -> &'static str {
+        self.datetime.timezone().name()
+    }
+
+    /// Returns the offset from UTC in effect at this instant.
+    pub fn offset(&self) -> chrono::FixedOffset {
+        self.datetime.offset().fix()
+    }
+
+    /// Converts the same instant to another time zone.
+    pub fn to_timezone(&self, timezone: &str) -> Result<ZonedDateX, DateError> {
+        match timezone.parse::<Tz>() {
+            Ok(tz) => Ok(ZonedDateX {
+                datetime: self.datetime.with_timezone(&tz),
+            }),
+            Err(_) => Err(DateError::InvalidTimezone(timezone.to_string())),
+        }
+    }
+
+    /// Drops the time zone, keeping the instant.
+    pub fn to_utc(&self) -> DateX {
+        DateX {
+            datetime: self.datetime.with_timezone(&Utc),
+        }
+    }
+
+    /// Formats the local time according to the specified format string.
+    pub fn format<F: DateFormat>(&self, format: F) -> String {
+        format.format_datetime(&self.datetime)
+    }
+
+    /// Formats the local time using the month and weekday names, AM/PM markers and
+    /// ordinals of the given locale.
+    pub fn format_with_locale(&self, format_str: &str, locale_code: &str) -> String {
+        let locale = LocaleManager::new();
+        DateFormatter::format_localized(&self.datetime, format_str, &locale, locale_code)
+    }
+
+    /// Returns the local year.
+    pub fn year(&self) -> i32 {
+        self.datetime.year()
+    }
+
+    /// Returns the local month (1-12).
+    pub fn month(&self) -> u32 {
+        self.datetime.month()
+    }
+
+    /// Returns the local day of the month (1-31).
+    pub fn day(&self) -> u32 {
+        self.datetime.day()
+    }
+
+    /// Returns the local hour (0-23).
+    pub fn hour(&self) -> u32 {
+        self.datetime.hour()
+    }
+
+    /// Returns the local minute (0-59).
+    pub fn minute(&self) -> u32 {
+        self.datetime.minute()
+    }
+
+    /// Returns the second (0-59).
+    pub fn second(&self) -> u32 {
+        self.datetime.second()
+    }
+
+    /// Returns the millisecond (0-999).
+    pub fn millisecond(&self) -> u32 {
+        self.datetime.timestamp_subsec_millis()
+    }
+
+    /// Returns the local day of the week (Monday = 1, Sunday = 7).
+    pub fn day_of_week(&self) -> u32 {
+        self.datetime.weekday().number_from_monday()
+    }
+
+    /// Returns the ISO week number of the local date.
+    pub fn iso_week(&self) -> u32 {
+        self.datetime.iso_week().week()
+    }
+
+    /// Returns the quarter of the local year (1-4).
+    pub fn quarter(&self) -> u32 {
+        (self.month() - 1) / 3 + 1
+    }
+
+    /// Checks if the local year is a leap year.
+    pub fn is_leap_year(&self) -> bool {
+        is_leap_year(self.year())
+    }
+
+    /// Returns the number of days in the local month.
+    pub fn days_in_month(&self) -> u32 {
+        days_in_month(self.year(), self.month())
+    }
+
+    /// Checks if the local date falls on a weekend.
+    pub fn is_weekend(&self) -> bool {
+        let weekday = self.datetime.weekday();
+        weekday == chrono::Weekday::Sat || weekday == chrono::Weekday::Sun
+    }
+
+    /// Returns the timestamp in seconds.
+    pub fn timestamp(&self) -> i64 {
+        self.datetime.timestamp()
+    }
+
+    /// Returns the timestamp in milliseconds.
+    pub fn timestamp_millis(&self) -> i64 {
+        self.datetime.timestamp_millis()
+    }
+
+    /// Converts the date to ISO 8601 format with the local offset.
+    pub fn to_iso8601(&self) -> String {
+        self.datetime.to_rfc3339()
+    }
+
+    /// Returns the local wall-clock time as a NaiveDateTime.
+    pub fn to_naive_local(&self) -> NaiveDateTime {
+        self.datetime.naive_local()
+    }
+
+    /// Returns the date as a DateTime<Tz>.
+    pub fn to_datetime(&self) -> DateTime<Tz> {
+        self.datetime
+    }
+
+    /// Adds a duration. Years, months, weeks and days move the local wall-clock
+    /// time, clamping the day to the end of a shorter target month; smaller units
+    /// move the instant.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the result is outside the supported range of dates.
+    pub fn add_duration(&mut self, amount: i64, unit: DurationUnit) {
+        match unit {
+            DurationUnit::Years
+            | DurationUnit::Months
+            | DurationUnit::Weeks
+            | DurationUnit::Days => {
+                let local = Utc.from_utc_datetime(&self.datetime.naive_local());
+                let shifted = TimeManipulator::add_duration(local, amount, unit);
+                self.set_local(shifted.naive_utc());
+            }
+            _ => {
+                let utc = self.datetime.with_timezone(&Utc);
+                let shifted = TimeManipulator::add_duration(utc, amount, unit);
+                self.datetime = shifted.with_timezone(&self.timezone());
+            }
+        }
+    }
+
+    /// Subtracts a duration, see [`ZonedDateX::add_duration`].
+    pub fn subtract_duration(&mut self, amount: i64, unit: DurationUnit) {
+        self.add_duration(-amount, unit);
+    }
+
+    /// Sets the date to the start of the local day.
+    pub fn start_of_day(&mut self) {
+        let naive = self
+            .datetime
+            .naive_local()
+            .date()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        self.set_local(naive);
+    }
+
+    /// Sets the date to the end of the local day.
+    pub fn end_of_day(&mut self) {
+        let naive = self
+            .datetime
+            .naive_local()
+            .date()
+            .and_hms_nano_opt(23, 59, 59, 999_999_999)
+            .unwrap();
+        self.set_local(naive);
+    }
+
+    /// Sets the date to the start of the local month.
+    pub fn start_of_month(&mut self) {
+        let naive = self.datetime.naive_local().with_day(1).unwrap();
+        self.set_local(naive);
+        self.start_of_day();
+    }
+
+    /// Sets the date to the end of the local month.
+    pub fn end_of_month(&mut self) {
+        let days_in_month = self.days_in_month();
+        let naive = self.datetime.naive_local().with_day(days_in_month).unwrap();
+        self.set_local(naive);
+        self.end_of_day();
+    }
+
+    /// Sets the date to the start of the local year.
+    pub fn start_of_year(&mut self) {
+        let naive = self
+            .datetime
+            .naive_local()
+            .with_day(1)
+            .and_then(|dt| dt.with_month(1))
+            .unwrap();
+        self.set_local(naive);
+        self.start_of_day();
+    }
+
+    /// Sets the date to the end of the local year.
+    pub fn end_of_year(&mut self) {
+        let naive = self
+            .datetime
+            .naive_local()
+            .with_month(12)
+            .and_then(|dt| dt.with_day(31))
+            .unwrap();
+        self.set_local(naive);
+        self.end_of_day();
+    }
+
+    /// Moves to a new local wall-clock time in the same zone, shifting times that
+    /// fall in a DST gap forward. Only `LocalTimePolicy::Reject` makes
+    /// `resolve_local` fail, so the result is always a valid instant.
+    fn set_local(&mut self, naive: NaiveDateTime) {
+        let policy = LocalTimePolicy::ShiftForward;
+        self.datetime = TimeZoneHandler::resolve_local(self.timezone(), naive, policy)
+            .expect("ShiftForward resolves every local time");
+    }
+}
+
+impl fmt::Display for ZonedDateX {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let formatted = self.format("%Y-%m-%d %H:%M:%S %Z");
+        write!(f, "{}", formatted)
+    }
+}
+
+impl From<ZonedDateX> for DateX {
+    fn from(zoned: ZonedDateX) -> Self {
+        zoned.to_utc()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zoned(local: &str, timezone: &str) -> ZonedDateX {
+        let naive = NaiveDateTime::parse_from_str(local, "%Y-%m-%d %H:%M").unwrap();
+        ZonedDateX::from_local(naive, timezone, LocalTimePolicy::Reject).unwrap()
+    }
+
+    fn local(date: &ZonedDateX) -> String {
+        date.format("%Y-%m-%d %H:%M %z")
+    }
+
+    #[test]
+    fn months_clamp_to_the_end_of_the_target_month() {
+        let mut date = zoned("2024-01-31 09:00", "Europe/Berlin");
+        date.add_duration(1, DurationUnit::Months);
+        assert_eq!(local(&date), "2024-02-29 09:00 +0100");
+        date.add_duration(1, DurationUnit::Months);
+        assert_eq!(local(&date), "2024-03-29 09:00 +0100");
+
+        let mut date = zoned("2024-03-31 09:00", "Europe/Berlin");
+        date.subtract_duration(1, DurationUnit::Months);
+        assert_eq!(local(&date), "2024-02-29 09:00 +0100");
+    }
+
+    #[test]
+    fn years_clamp_leap_days() {
+        let mut date = zoned("2024-02-29 12:00", "America/New_York");
+        date.add_duration(1, DurationUnit::Years);
+        assert_eq!(local(&date), "2025-02-28 12:00 -0500");
+    }
+
+    #[test]
+    fn days_landing_in_a_gap_shift_forward() {
+        let mut date = zoned("2024-03-09 02:30", "America/New_York");
+        date.add_duration(1, DurationUnit::Days);
+        assert_eq!(local(&date), "2024-03-10 03:30 -0400");
+    }
+}